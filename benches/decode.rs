@@ -0,0 +1,108 @@
+//! Throughput comparison of the encode and decode paths.
+//!
+//! Every available path runs over identical inputs at a few sizes:
+//! - `scalar`: the original bit accumulator, `base64_encode` and the
+//!   decoder kept for `Base64Config::legacy`.
+//! - `unrolled`: the group at a time code, `Base64Config::encode` and
+//!   `decode_strict_aligned`.
+//! - `unchecked`: `decode_groups_unchecked`, timed on the unpadded groups
+//!   of the same input.
+//! - `uninit` and `zeroed`: `decode_uninit` into a freshly allocated
+//!   buffer, left uninitialized or zero filled first.
+//! - `config`: the configurable byte at a time decoder behind
+//!   `Base64Config::decode` and `base64_decode`.
+//! - `simd`: `decode_simd`, which uses AVX2, SSSE3 or NEON where available
+//!   and otherwise falls back to the `unrolled` decoder; its benchmark is
+//!   named after the path it takes, e.g. `simd-avx2`.
+//!
+//! The `encode` and `decode` criterion groups report throughput in bytes
+//! of encoded data. Run with `cargo bench --bench decode`.
+
+use std::hint::black_box;
+use std::mem::MaybeUninit;
+
+use base64::{
+    base64_decode, base64_encode, decode_groups_unchecked, decode_simd, decode_strict_aligned,
+    decode_uninit, simd_path, Base64Config,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: [usize; 3] = [64, 4 * 1024, 1024 * 1024];
+
+/// The input of `size` bytes, and its encoding.
+fn input(size: usize) -> (Vec<u8>, String) {
+    let data: Vec<u8> = (0..size).map(|i| (i * 31) as u8).collect();
+    let encoded = base64_encode(&data);
+    assert_eq!(Base64Config::STANDARD.encode(&data), encoded);
+    (data, encoded)
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for size in SIZES {
+        let (data, encoded) = input(size);
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", size), &data, |b, data| {
+            b.iter(|| base64_encode(black_box(data)))
+        });
+        group.bench_with_input(BenchmarkId::new("unrolled", size), &data, |b, data| {
+            b.iter(|| Base64Config::STANDARD.encode(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let simd = format!("simd-{}", simd_path());
+    #[allow(deprecated)]
+    let legacy = Base64Config::legacy();
+
+    let mut group = c.benchmark_group("decode");
+    for size in SIZES {
+        let (data, encoded) = input(size);
+        let encoded = encoded.as_str();
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", size), encoded, |b, encoded| {
+            b.iter(|| legacy.decode(black_box(encoded.as_bytes())).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("config", size), encoded, |b, encoded| {
+            b.iter(|| base64_decode(black_box(encoded)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("unrolled", size), encoded, |b, encoded| {
+            b.iter(|| decode_strict_aligned(black_box(encoded)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new(&simd, size), encoded, |b, encoded| {
+            b.iter(|| decode_simd(black_box(encoded)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("uninit", size), encoded, |b, encoded| {
+            b.iter(|| {
+                let mut buffer = Vec::with_capacity(data.len() + 2);
+                decode_uninit(black_box(encoded), buffer.spare_capacity_mut())
+                    .unwrap()
+                    .len()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("zeroed", size), encoded, |b, encoded| {
+            b.iter(|| {
+                let mut buffer = vec![MaybeUninit::new(0); data.len() + 2];
+                decode_uninit(black_box(encoded), &mut buffer)
+                    .unwrap()
+                    .len()
+            })
+        });
+
+        let groups = &encoded.as_bytes()[..data.len() / 3 * 4];
+        let mut out = vec![0u8; groups.len() / 4 * 3];
+        group.throughput(Throughput::Bytes(groups.len() as u64));
+        group.bench_with_input(BenchmarkId::new("unchecked", size), groups, |b, groups| {
+            b.iter(|| {
+                decode_groups_unchecked(black_box(groups), &mut out);
+                black_box(&out);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Heap allocations and time spent decoding tiny tokens.
+//!
+//! Compares `base64_decode`, which always returns a `Vec`, with
+//! `decode_small`, which keeps outputs of up to `INLINE_CAPACITY` bytes
+//! inline. Allocations are counted by wrapping the system allocator.
+//!
+//! Run with `cargo bench --bench small`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use base64::{base64_decode, base64_encode, decode_small, INLINE_CAPACITY};
+
+/// The system allocator, counting every allocation it makes.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Runs `f` over every token repeatedly for roughly half a second and
+/// prints the time and allocations per decode.
+fn bench(name: &str, tokens: &[String], mut f: impl FnMut(&str)) {
+    let start = Instant::now();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut decodes = 0u32;
+    while start.elapsed() < Duration::from_millis(500) {
+        for token in tokens {
+            f(token);
+        }
+        decodes += tokens.len() as u32;
+    }
+    let per_decode = start.elapsed() / decodes;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{:<24} {:>10.2?}/decode {:>8.3} allocations/decode",
+        name,
+        per_decode,
+        allocations as f64 / decodes as f64
+    );
+}
+
+fn main() {
+    let tokens: Vec<String> = (0..INLINE_CAPACITY)
+        .map(|len| base64_encode(&vec![0x5a; len]))
+        .collect();
+
+    println!("-- tokens of 0 to {} bytes --", INLINE_CAPACITY - 1);
+    bench("decode/vec", &tokens, |token| {
+        black_box(base64_decode(black_box(token)).unwrap());
+    });
+    bench("decode/small", &tokens, |token| {
+        black_box(decode_small(black_box(token)).unwrap());
+    });
+}
@@ -0,0 +1,476 @@
+//! The 64 character sets used to encode sextets
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{CHARSET, INVALID, PADDING};
+
+/// A Base64 alphabet: the 64 characters sextets map to, along with the
+/// reverse lookup used when decoding.
+///
+/// Example:
+/// ```
+/// use base64::{Alphabet, Base64Config};
+///
+/// let config = Base64Config::new().alphabet(Alphabet::URL_SAFE);
+/// assert_eq!(config.encode(b"\xfb\xff"), "-_8=");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    chars: [u8; 64],
+    decode: [u8; 256],
+}
+
+impl Alphabet {
+    /// The standard alphabet from RFC 4648 §4, `A-Z a-z 0-9 + /`.
+    pub const STANDARD: Alphabet = Alphabet::new(CHARSET);
+
+    /// The URL and filename safe alphabet from RFC 4648 §5, which uses
+    /// `-` and `_` in place of `+` and `/`.
+    pub const URL_SAFE: Alphabet =
+        Alphabet::new(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_");
+
+    /// An alphabet whose characters are in ASCII order, `- 0-9 A-Z _ a-z`,
+    /// so that encoded strings sort the same way as the bytes they encode.
+    ///
+    /// This only holds without padding, as `=` sorts above `-` and the
+    /// digits: use it with `pad(false)`. Example:
+    /// ```
+    /// use base64::{Alphabet, Base64Config};
+    ///
+    /// let config = Base64Config::new().alphabet(Alphabet::SORTABLE).pad(false);
+    /// assert!(config.encode(b"\x00\xff") < config.encode(b"\x01"));
+    /// ```
+    pub const SORTABLE: Alphabet =
+        Alphabet::new(b"-0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz");
+
+    /// The ordering of traditional Unix `crypt(3)` hashes,
+    /// `. / 0-9 A-Z a-z`.
+    ///
+    /// This is for reading and writing password hash storage formats, not
+    /// for general Base64: those formats never pad, so use it with
+    /// `pad(false)`. Only the characters are covered; the SHA-crypt family
+    /// additionally reorders the hash bytes before encoding them.
+    pub const CRYPT: Alphabet =
+        Alphabet::new(b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz");
+
+    /// The ordering used by bcrypt hashes, `. / A-Z a-z 0-9`.
+    ///
+    /// Like `CRYPT`, this is for password hash interop rather than general
+    /// Base64, and bcrypt never pads. Example:
+    /// ```
+    /// use base64::{Alphabet, Base64Config};
+    ///
+    /// let config = Base64Config::new().alphabet(Alphabet::BCRYPT).pad(false);
+    /// // The salt of `$2a$10$N9qo8uLOickgx2ZMRZoMyeIjZAgcfl7p92ldGxad68LJZdL17lhWy`
+    /// let salt = config.decode(b"N9qo8uLOickgx2ZMRZoMye").unwrap();
+    /// assert_eq!(salt.len(), 16);
+    /// assert_eq!(config.encode(&salt), "N9qo8uLOickgx2ZMRZoMye");
+    /// ```
+    pub const BCRYPT: Alphabet =
+        Alphabet::new(b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789");
+
+    /// An alphabet for codes that people read out and type in, such as
+    /// coupon codes, in the spirit of Crockford's Base32.
+    ///
+    /// Decoding ignores case, and the lookalikes `I`, `L` and `O` are left
+    /// out: decoding reads `I` and `L` as `1` and `O` as `0`, in either
+    /// case. Once case is ignored the letters and digits only provide 33
+    /// symbols, so the other 31 are every printable ASCII punctuation
+    /// character but `=`. Encoded codes therefore need percent-encoding in
+    /// a URL and quoting in a shell. The characters are in ASCII order, so
+    /// encoded codes sort like their bytes.
+    ///
+    /// Example:
+    /// ```
+    /// use base64::{Alphabet, Base64Config};
+    ///
+    /// let config = Base64Config::new().alphabet(Alphabet::HUMAN).pad(false);
+    /// assert_eq!(config.encode(b"\xa1\x0b\xd6"), "K1T7");
+    /// assert_eq!(config.decode(b"klt7").unwrap(), b"\xa1\x0b\xd6");
+    /// ```
+    pub const HUMAN: Alphabet =
+        Alphabet::new(b"!\"#$%&'()*+,-./0123456789:;<>?@ABCDEFGHJKMNPQRSTUVWXYZ[\\]^_`{|}~")
+            .fold_case()
+            .alias(b'I', b'1')
+            .alias(b'i', b'1')
+            .alias(b'L', b'1')
+            .alias(b'l', b'1')
+            .alias(b'O', b'0')
+            .alias(b'o', b'0');
+
+    /// Builds an alphabet from its 64 characters, in sextet order.
+    ///
+    /// Panics (at compile time when used in a `const`) if a character is
+    /// not ASCII, is the padding character, or appears more than once.
+    pub const fn new(chars: &[u8; 64]) -> Alphabet {
+        let mut decode = [INVALID; 256];
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c >= 0x80 {
+                panic!("Base64 alphabet characters must be ASCII");
+            }
+            if c == PADDING as u8 {
+                panic!("Base64 alphabet must not contain the padding character");
+            }
+            if decode[c as usize] != INVALID {
+                panic!("Base64 alphabet characters must be distinct");
+            }
+            decode[c as usize] = i as u8;
+            i += 1;
+        }
+        Alphabet {
+            chars: *chars,
+            decode,
+        }
+    }
+
+    /// Also decodes `alias` as the alphabet's character `target`.
+    const fn alias(mut self, alias: u8, target: u8) -> Alphabet {
+        self.decode[alias as usize] = self.decode[target as usize];
+        self
+    }
+
+    /// Also decodes each lowercase letter as its uppercase form, for the
+    /// uppercase letters the alphabet has.
+    const fn fold_case(mut self) -> Alphabet {
+        let mut upper = b'A';
+        while upper <= b'Z' {
+            if self.contains(upper) {
+                self = self.alias(upper.to_ascii_lowercase(), upper);
+            }
+            upper += 1;
+        }
+        self
+    }
+
+    /// Returns the 64 characters of the alphabet, in sextet order.
+    pub const fn chars(&self) -> &[u8; 64] {
+        &self.chars
+    }
+
+    /// Returns which RFC 4648 alphabet this is, if either.
+    pub fn variant(&self) -> Option<Variant> {
+        if *self == Alphabet::STANDARD {
+            Some(Variant::Standard)
+        } else if *self == Alphabet::URL_SAFE {
+            Some(Variant::UrlSafe)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether `byte` decodes to a sextet.
+    pub(crate) const fn contains(&self, byte: u8) -> bool {
+        self.decode[byte as usize] != INVALID
+    }
+
+    /// Returns the character for a sextet value (0-63).
+    pub(crate) const fn encode_sextet(&self, sextet: u8) -> u8 {
+        self.chars[(sextet & 0b111111) as usize]
+    }
+
+    /// Returns the sextet value of `byte`, or `INVALID` if it is not part
+    /// of the alphabet.
+    pub(crate) const fn decode_byte(&self, byte: u8) -> u8 {
+        self.decode[byte as usize]
+    }
+}
+
+/// Characters for an alphabet can be read at runtime, e.g. from a config
+/// file, with `str::parse`.
+///
+/// Example:
+/// ```
+/// use base64::{Alphabet, Base64Config};
+///
+/// let chars = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789.~";
+/// let alphabet: Alphabet = chars.parse().unwrap();
+/// let config = Base64Config::new().alphabet(alphabet).pad(false);
+/// assert_eq!(config.encode(b"\xfb\xff"), ".~8");
+/// ```
+impl FromStr for Alphabet {
+    type Err = AlphabetError;
+
+    /// Builds an alphabet from its 64 characters, in sextet order, checking
+    /// the same rules as `Alphabet::new` without panicking.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((index, c)) = s.chars().enumerate().find(|(_, c)| !c.is_ascii()) {
+            return Err(AlphabetError::NonAscii { index, c });
+        }
+        let chars: &[u8; 64] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| AlphabetError::WrongLength { len: s.len() })?;
+
+        for (index, &c) in chars.iter().enumerate() {
+            if c == PADDING as u8 {
+                return Err(AlphabetError::Padding { index });
+            }
+            if let Some(first) = chars[..index].iter().position(|&other| other == c) {
+                return Err(AlphabetError::Duplicate {
+                    index,
+                    first,
+                    c: c as char,
+                });
+            }
+        }
+        Ok(Alphabet::new(chars))
+    }
+}
+
+/// The reason a string could not be parsed into an `Alphabet`.
+///
+/// Indices count characters from the start of the string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AlphabetError {
+    /// The string does not hold exactly 64 characters.
+    WrongLength { len: usize },
+    /// A character is not ASCII.
+    NonAscii { index: usize, c: char },
+    /// The padding character `=` is part of the string.
+    Padding { index: usize },
+    /// A character already appeared at index `first`.
+    Duplicate { index: usize, first: usize, c: char },
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphabetError::WrongLength { len } => write!(
+                f,
+                "Invalid base64 alphabet: Expected 64 characters, found {}.",
+                len
+            ),
+            AlphabetError::NonAscii { index, c } => write!(
+                f,
+                "Invalid base64 alphabet: Expected ASCII, found '{}' at index {}.",
+                c, index
+            ),
+            AlphabetError::Padding { index } => write!(
+                f,
+                "Invalid base64 alphabet: Padding character '{}' at index {}.",
+                PADDING, index
+            ),
+            AlphabetError::Duplicate { index, first, c } => write!(
+                f,
+                "Invalid base64 alphabet: '{}' at index {} already appears at index {}.",
+                c, index, first
+            ),
+        }
+    }
+}
+
+impl Error for AlphabetError {}
+
+/// The two alphabets defined by RFC 4648, as named in error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// `Alphabet::STANDARD`, with `+` and `/`.
+    Standard,
+    /// `Alphabet::URL_SAFE`, with `-` and `_`.
+    UrlSafe,
+}
+
+impl Variant {
+    /// Returns whether `byte` is one of the characters only the other
+    /// variant uses, which suggests the two were mixed up.
+    pub(crate) fn confused_by(self, byte: u8) -> bool {
+        match self {
+            Variant::Standard => byte == b'-' || byte == b'_',
+            Variant::UrlSafe => byte == b'+' || byte == b'/',
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "standard"),
+            Variant::UrlSafe => write!(f, "URL-safe"),
+        }
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::STANDARD
+    }
+}
+
+impl fmt::Debug for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The characters are all ASCII, so this never loses information.
+        f.debug_tuple("Alphabet")
+            .field(&String::from_utf8_lossy(&self.chars))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Base64Config;
+
+    #[test]
+    fn reverse_lookup_matches_chars() {
+        for alphabet in [Alphabet::STANDARD, Alphabet::URL_SAFE] {
+            for (i, &c) in alphabet.chars().iter().enumerate() {
+                assert_eq!(alphabet.decode_byte(c), i as u8);
+                assert_eq!(alphabet.encode_sextet(i as u8), c);
+            }
+            assert_eq!(alphabet.decode_byte(PADDING as u8), INVALID);
+        }
+        assert_eq!(Alphabet::URL_SAFE.decode_byte(b'+'), INVALID);
+        assert_eq!(Alphabet::STANDARD.decode_byte(b'_'), INVALID);
+    }
+
+    #[test]
+    fn sortable_alphabet_preserves_byte_order() {
+        assert!(Alphabet::SORTABLE.chars().is_sorted());
+
+        let config = crate::Base64Config::new()
+            .alphabet(Alphabet::SORTABLE)
+            .pad(false);
+        // A fixed xorshift sequence, so failures are reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..2000 {
+            let (x, y) = (next(), next());
+            let a = &x.to_be_bytes()[..(y % 9) as usize];
+            let b = &y.to_be_bytes()[..(x % 9) as usize];
+            // Sharing a prefix makes the later bytes decide the order.
+            let b = [a, b].concat();
+            for (a, b) in [(a, &b[..]), (&b[..], a), (a, &x.to_le_bytes()[..])] {
+                assert_eq!(a.cmp(b), config.encode(a).cmp(&config.encode(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn human_alphabet_ignores_case_and_lookalikes() {
+        let config = crate::Base64Config::new()
+            .alphabet(Alphabet::HUMAN)
+            .pad(false);
+        let data: Vec<u8> = (0..=255u8).chain((0..=255u8).rev()).collect();
+        let encoded = config.encode(&data);
+        assert!(!encoded.contains(['I', 'L', 'O', '=']));
+        assert!(!encoded.contains(|c: char| c.is_ascii_lowercase()));
+        assert!(Alphabet::HUMAN
+            .chars()
+            .iter()
+            .all(|c| c.is_ascii_graphic() && !c.is_ascii_lowercase()));
+        assert_eq!(config.decode(encoded.as_bytes()).unwrap(), data);
+
+        let lowercase = encoded.to_ascii_lowercase();
+        assert_eq!(config.decode(lowercase.as_bytes()).unwrap(), data);
+        for (typed, meant) in [("ABCD", "abcd"), ("IiLl", "1111"), ("Oo{}", "00{}")] {
+            assert_eq!(
+                config.decode(typed.as_bytes()),
+                config.decode(meant.as_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn human_alphabet_sorts_like_its_bytes() {
+        let chars = Alphabet::HUMAN.chars();
+        assert!(chars.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn hash_alphabets_round_trip() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for alphabet in [Alphabet::CRYPT, Alphabet::BCRYPT] {
+            let config = Base64Config::new().alphabet(alphabet.clone()).pad(false);
+            for len in 0..40 {
+                let encoded = config.encode(&data[..len]);
+                assert!(encoded.bytes().all(|c| alphabet.contains(c)));
+                assert_eq!(config.decode(encoded.as_bytes()).unwrap(), &data[..len]);
+            }
+            assert_eq!(config.encode(&[0; 3]), "....");
+            assert_eq!(alphabet.variant(), None);
+        }
+
+        let crypt = Base64Config::new().alphabet(Alphabet::CRYPT).pad(false);
+        assert_eq!(crypt.encode(b"Many hands"), "HK3iSG/cMKtYQk");
+
+        let bcrypt = Base64Config::new().alphabet(Alphabet::BCRYPT).pad(false);
+        let hash = bcrypt.decode(b"IjZAgcfl7p92ldGxad68LJZdL17lhWy").unwrap();
+        assert_eq!(hash.len(), 23);
+        assert_eq!(hash[..4], [0x2a, 0x56, 0xc2, 0x89]);
+        assert_eq!(bcrypt.encode(&hash), "IjZAgcfl7p92ldGxad68LJZdL17lhWy");
+    }
+
+    #[test]
+    fn builtin_alphabets_know_their_variant() {
+        assert_eq!(Alphabet::STANDARD.variant(), Some(Variant::Standard));
+        assert_eq!(Alphabet::URL_SAFE.variant(), Some(Variant::UrlSafe));
+        let mut chars = *CHARSET;
+        chars.swap(0, 1);
+        assert_eq!(Alphabet::new(&chars).variant(), None);
+    }
+
+    #[test]
+    fn parsed_alphabets_match_built_ones() {
+        let standard = std::str::from_utf8(CHARSET).unwrap();
+        assert_eq!(standard.parse::<Alphabet>(), Ok(Alphabet::STANDARD));
+        let url_safe = std::str::from_utf8(Alphabet::URL_SAFE.chars()).unwrap();
+        assert_eq!(url_safe.parse::<Alphabet>(), Ok(Alphabet::URL_SAFE));
+    }
+
+    #[test]
+    fn parsing_reports_each_problem() {
+        let standard = std::str::from_utf8(CHARSET).unwrap();
+        assert_eq!(
+            standard[1..].parse::<Alphabet>(),
+            Err(AlphabetError::WrongLength { len: 63 })
+        );
+        assert_eq!(
+            format!("{}A", standard).parse::<Alphabet>(),
+            Err(AlphabetError::WrongLength { len: 65 })
+        );
+        assert_eq!(
+            "".parse::<Alphabet>(),
+            Err(AlphabetError::WrongLength { len: 0 })
+        );
+        assert_eq!(
+            format!("{}\u{e9}", &standard[..63]).parse::<Alphabet>(),
+            Err(AlphabetError::NonAscii {
+                index: 63,
+                c: '\u{e9}'
+            })
+        );
+        assert_eq!(
+            format!("{}=", &standard[..63]).parse::<Alphabet>(),
+            Err(AlphabetError::Padding { index: 63 })
+        );
+        let duplicate = format!("{}B", &standard[..63]);
+        let err = duplicate.parse::<Alphabet>().unwrap_err();
+        assert_eq!(
+            err,
+            AlphabetError::Duplicate {
+                index: 63,
+                first: 1,
+                c: 'B'
+            }
+        );
+        assert!(err.to_string().starts_with("Invalid base64 alphabet"));
+    }
+
+    #[test]
+    #[should_panic(expected = "distinct")]
+    fn duplicate_characters_are_rejected() {
+        let mut chars = *CHARSET;
+        chars[63] = b'A';
+        Alphabet::new(&chars);
+    }
+}
@@ -0,0 +1,282 @@
+//! Decoding many Base64 strings in one call
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{base64_decode, encode_chain, DecodeError, PADDING};
+
+/// Decodes every item in `items`, one after another.
+///
+/// Parameters:
+/// - `items`: The Base64 encoded strings to decode.
+///
+/// Returns: One decode result per item, in the same order as `items`.
+pub fn decode_many(items: &[&str]) -> Vec<Result<Vec<u8>, DecodeError>> {
+    items.iter().map(|item| base64_decode(item)).collect()
+}
+
+/// Decodes every item in `items`, stopping at the first one that fails.
+///
+/// Parameters:
+/// - `items`: The Base64 encoded strings to decode.
+///
+/// Returns: The decoded items in order, or the position of the first
+/// invalid item with its `DecodeError`. Items after it are not decoded.
+pub fn decode_batch(items: &[&str]) -> Result<Vec<Vec<u8>>, (usize, DecodeError)> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| base64_decode(item).map_err(|err| (i, err)))
+        .collect()
+}
+
+/// Decodes records joined by `sep`, as stored by some legacy systems,
+/// e.g. `QUJD|RUZH`.
+///
+/// An empty record, between two separators or at either end, decodes to
+/// no bytes, so a record's position in the output always matches its
+/// position in `data`. Empty `data` holds no records at all.
+///
+/// Parameters:
+/// - `data`: The records, separated by `sep`.
+/// - `sep`: The separator; it should not be part of the alphabet.
+///
+/// Returns: The decoded records in order, or the position of the first
+/// invalid record (counting from 0) with its `DecodeError`. The error's
+/// index counts from the start of that record.
+pub fn decode_separated(data: &str, sep: char) -> Result<Vec<Vec<u8>>, (usize, DecodeError)> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    data.split(sep)
+        .enumerate()
+        .map(|(i, record)| base64_decode(record).map_err(|err| (i, err)))
+        .collect()
+}
+
+/// Decodes padded records concatenated without any separator, e.g.
+/// `QQ==QUJD`.
+///
+/// Each record ends with its run of padding, however long, so `QUI=QQ==`
+/// holds `QUI=` and `QQ==`. Whatever follows the last run is the final
+/// record. A record that needs no padding has no visible end, so it is
+/// joined to the record after it: `QUJDQQ==` is a single record.
+///
+/// Parameters:
+/// - `data`: The concatenated records.
+///
+/// Returns: The decoded records in order, or the first `DecodeError`, with
+/// its index counted from the start of `data`.
+pub fn decode_multi_record(data: &str) -> Result<Vec<Vec<u8>>, DecodeError> {
+    let bytes = data.as_bytes();
+    let mut records = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = match bytes[start..].iter().position(|&b| b == PADDING as u8) {
+            Some(pad) => {
+                let pad = start + pad;
+                pad + bytes[pad..]
+                    .iter()
+                    .take_while(|&&b| b == PADDING as u8)
+                    .count()
+            }
+            None => bytes.len(),
+        };
+        // Records end at padding, so they are whole characters.
+        let record = base64_decode(&data[start..end]).map_err(|err| err.shifted(start))?;
+        records.push(record);
+        start = end;
+    }
+    Ok(records)
+}
+
+/// Joins separately encoded fragments into one valid Base64 string.
+///
+/// Simply concatenating padded fragments leaves padding in the middle,
+/// and the groups after it misaligned. Instead every part is decoded and
+/// the concatenated bytes are encoded again, so only the end is padded.
+///
+/// Example:
+/// ```
+/// use base64::concat_aligned;
+///
+/// let parts = ["TQ==".to_string(), "YW4=".to_string()];
+/// assert_eq!(concat_aligned(&parts).unwrap(), "TWFu");
+/// ```
+///
+/// Parameters:
+/// - `parts`: The encoded fragments, in order.
+///
+/// Returns: The encoding of all the fragments' bytes, or the position of
+/// the first invalid fragment with its `DecodeError`.
+pub fn concat_aligned(parts: &[String]) -> Result<String, (usize, DecodeError)> {
+    let items: Vec<&str> = parts.iter().map(String::as_str).collect();
+    let decoded = decode_batch(&items)?;
+    let sources: Vec<&[u8]> = decoded.iter().map(Vec::as_slice).collect();
+    Ok(encode_chain(&sources))
+}
+
+/// Decodes every value of `map`, keeping the keys.
+///
+/// Parameters:
+/// - `map`: Keys mapped to Base64 encoded values, e.g. fields of a config
+///   file.
+///
+/// Returns: The keys mapped to their decoded values, or the key of a value
+/// that failed to decode together with its `DecodeError`. If several values
+/// are invalid, which one is reported depends on the map's iteration order.
+pub fn decode_map(
+    map: &HashMap<String, String>,
+) -> Result<HashMap<String, Vec<u8>>, (String, DecodeError)> {
+    map.iter().map(decode_entry).collect()
+}
+
+/// Like [`decode_map`], but for ordered maps. The reported error is always
+/// that of the first invalid value in key order.
+pub fn decode_btree_map(
+    map: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, Vec<u8>>, (String, DecodeError)> {
+    map.iter().map(decode_entry).collect()
+}
+
+/// Decodes one map value, attributing any error to its key.
+fn decode_entry(
+    (key, value): (&String, &String),
+) -> Result<(String, Vec<u8>), (String, DecodeError)> {
+    match base64_decode(value) {
+        Ok(decoded) => Ok((key.clone(), decoded)),
+        Err(err) => Err((key.clone(), err)),
+    }
+}
+
+/// Decodes every item in `items`, spreading the work across threads.
+///
+/// The items are decoded on rayon's global thread pool. The output is
+/// identical to [`decode_many`], so call sites can switch between the two
+/// freely.
+///
+/// Parameters:
+/// - `items`: The Base64 encoded strings to decode.
+///
+/// Returns: One decode result per item, in the same order as `items`.
+#[cfg(feature = "rayon")]
+pub fn decode_many_parallel(items: &[&str]) -> Vec<Result<Vec<u8>, DecodeError>> {
+    use rayon::prelude::*;
+
+    items.par_iter().map(|item| base64_decode(item)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_many_keeps_order() {
+        let results = decode_many(&["QUJD", "$$$$", "", "RA=="]);
+        assert_eq!(results[0], Ok(b"ABC".to_vec()));
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Ok(Vec::new()));
+        assert_eq!(results[3], Ok(b"D".to_vec()));
+    }
+
+    #[test]
+    fn batch_stops_at_the_first_bad_item() {
+        assert_eq!(
+            decode_batch(&["QUJD", "RA==", "RU$H", "!!!!"]),
+            Err((2, DecodeError::invalid_byte(2, b'$')))
+        );
+        assert_eq!(
+            decode_batch(&["QUJD", "", "RA=="]).unwrap(),
+            [&b"ABC"[..], b"", b"D"]
+        );
+    }
+
+    #[test]
+    fn separated_records_decode_in_order() {
+        assert_eq!(
+            decode_separated("QUJD|RUZH|SElK", '|').unwrap(),
+            [b"ABC", b"EFG", b"HIJ"]
+        );
+        assert_eq!(
+            decode_separated("QUJD||RA==|", '|').unwrap(),
+            [&b"ABC"[..], b"", b"D", b""]
+        );
+        assert!(decode_separated("", '|').unwrap().is_empty());
+        assert_eq!(
+            decode_separated("QUJD|RUZH|SE$K", '|'),
+            Err((2, DecodeError::invalid_byte(2, b'$')))
+        );
+    }
+
+    #[test]
+    fn multi_record_splits_after_each_padding_run() {
+        assert_eq!(
+            decode_multi_record("QQ==QUJD").unwrap(),
+            [&[0x41][..], &[0x41, 0x42, 0x43]]
+        );
+        assert_eq!(
+            decode_multi_record("QUI=QQ==QUJDRA==").unwrap(),
+            [&b"AB"[..], b"A", b"ABCD"]
+        );
+        assert!(decode_multi_record("").unwrap().is_empty());
+        assert_eq!(
+            decode_multi_record("QQ==QU$D"),
+            Err(DecodeError::invalid_byte(6, b'$'))
+        );
+        assert_eq!(
+            decode_multi_record("QQ==Q==="),
+            Err(DecodeError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn concatenated_fragments_are_realigned() {
+        let payloads: [&[u8]; 3] = [b"Ma", b"ny h", b"ands"];
+        let parts: Vec<String> = payloads.iter().map(|p| crate::base64_encode(p)).collect();
+        assert_eq!(parts, ["TWE=", "bnkgaA==", "YW5kcw=="]);
+
+        let joined = concat_aligned(&parts).unwrap();
+        assert_eq!(joined, "TWFueSBoYW5kcw==");
+        assert_eq!(base64_decode(&joined).unwrap(), b"Many hands");
+        assert!(base64_decode(&parts.concat()).is_err());
+
+        assert_eq!(concat_aligned(&[]).unwrap(), "");
+        assert_eq!(
+            concat_aligned(&["TWE=".to_string(), "bn$=".to_string()]),
+            Err((1, DecodeError::invalid_byte(2, b'$')))
+        );
+    }
+
+    #[test]
+    fn map_errors_name_the_key() {
+        let mut map = HashMap::new();
+        map.insert("user".to_string(), "YWxpY2U=".to_string());
+        map.insert("token".to_string(), "c2Vj!mV0".to_string());
+        assert_eq!(
+            decode_map(&map),
+            Err(("token".to_string(), DecodeError::invalid_byte(4, b'!')))
+        );
+
+        map.remove("token");
+        assert_eq!(decode_map(&map).unwrap()["user"], b"alice");
+
+        let ordered: BTreeMap<String, String> = [("a", "YQ=="), ("b", "$"), ("c", "#")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(decode_btree_map(&ordered).unwrap_err().0, "b");
+        assert!(decode_btree_map(&BTreeMap::new()).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        let encoded: Vec<String> = (0..500u32)
+            .map(|i| crate::base64_encode(&i.to_be_bytes()[..(i % 4) as usize]))
+            .chain(["not base64!".to_string()])
+            .collect();
+        let items: Vec<&str> = encoded.iter().map(String::as_str).collect();
+
+        assert_eq!(decode_many_parallel(&items), decode_many(&items));
+        assert!(decode_many_parallel(&[]).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Encoding across threads through a channel
+
+use std::sync::mpsc::{self, Receiver, SendError, SyncSender};
+
+use crate::Base64Encoder;
+
+/// How many encoded chunks may wait in the channel before `send` blocks.
+pub const CHANNEL_BOUND: usize = 16;
+
+/// Creates a channel that Base64 encodes the bytes sent into it.
+///
+/// Bytes sent on the `EncodeSender` arrive on the `EncodeReceiver` as
+/// chunks of encoded text, which concatenate to the encoding of all the
+/// bytes. Up to 2 bytes carry over from one `send` to the next; the last
+/// ones are padded when the sender is dropped, after which the receiver
+/// ends. At most `CHANNEL_BOUND` chunks are buffered, so a fast producer
+/// waits for a slow consumer instead of using up memory.
+///
+/// Example:
+/// ```
+/// use base64::channel_encoder;
+/// use std::thread;
+///
+/// let (mut sender, receiver) = channel_encoder();
+/// let producer = thread::spawn(move || {
+///     sender.send(b"Many ").unwrap();
+///     sender.send(b"hands").unwrap();
+/// });
+/// let encoded: String = receiver.collect();
+/// producer.join().unwrap();
+/// assert_eq!(encoded, "TWFueSBoYW5kcw==");
+/// ```
+pub fn channel_encoder() -> (EncodeSender, EncodeReceiver) {
+    let (sender, receiver) = mpsc::sync_channel(CHANNEL_BOUND);
+    (
+        EncodeSender {
+            encoder: Base64Encoder::new(),
+            sender,
+        },
+        EncodeReceiver { receiver },
+    )
+}
+
+/// The sending half of `channel_encoder`.
+#[derive(Debug)]
+pub struct EncodeSender {
+    encoder: Base64Encoder,
+    sender: SyncSender<String>,
+}
+
+impl EncodeSender {
+    /// Encodes `data` and sends every group that is now complete, blocking
+    /// while the channel is full.
+    ///
+    /// Returns: An error holding the encoded chunk if the receiver is gone.
+    pub fn send(&mut self, data: &[u8]) -> Result<(), SendError<String>> {
+        let encoded = self.encoder.update(data);
+        if encoded.is_empty() {
+            return Ok(());
+        }
+        self.sender.send(encoded)
+    }
+}
+
+impl Drop for EncodeSender {
+    /// Sends the padded final group. Nobody is left to hear about it if
+    /// the receiver is gone, so that is ignored.
+    fn drop(&mut self) {
+        let tail = std::mem::take(&mut self.encoder).finalize();
+        if !tail.is_empty() {
+            let _ = self.sender.send(tail);
+        }
+    }
+}
+
+/// The receiving half of `channel_encoder`, yielding encoded chunks until
+/// the sender is dropped.
+#[derive(Debug)]
+pub struct EncodeReceiver {
+    receiver: Receiver<String>,
+}
+
+impl Iterator for EncodeReceiver {
+    type Item = String;
+
+    /// Blocks until the next chunk arrives, or returns `None` once the
+    /// sender is dropped and everything has been received.
+    fn next(&mut self) -> Option<String> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_encode;
+    use std::thread;
+
+    #[test]
+    fn received_chunks_join_to_one_shot_encode() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let (mut sender, receiver) = channel_encoder();
+        let sent = data.clone();
+        let producer = thread::spawn(move || {
+            for (i, piece) in sent.chunks(1000).enumerate() {
+                // Uneven pieces, so groups span sends.
+                let (a, b) = piece.split_at(i % 5);
+                sender.send(a).unwrap();
+                sender.send(b).unwrap();
+            }
+        });
+
+        let chunks: Vec<String> = receiver.collect();
+        producer.join().unwrap();
+        assert!(chunks.len() > CHANNEL_BOUND);
+        assert_eq!(chunks.concat(), base64_encode(&data));
+    }
+
+    #[test]
+    fn dropping_the_sender_pads_and_ends_the_stream() {
+        let (mut sender, mut receiver) = channel_encoder();
+        sender.send(b"M").unwrap();
+        drop(sender);
+        assert_eq!(receiver.next().as_deref(), Some("TQ=="));
+        assert_eq!(receiver.next(), None);
+
+        let (sender, receiver) = channel_encoder();
+        drop(sender);
+        assert_eq!(receiver.count(), 0);
+    }
+
+    #[test]
+    fn sending_fails_once_the_receiver_is_gone() {
+        let (mut sender, receiver) = channel_encoder();
+        drop(receiver);
+        assert!(sender.send(b"").is_ok());
+        assert_eq!(sender.send(b"Man").unwrap_err().0, "TWFu");
+    }
+}
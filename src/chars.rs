@@ -0,0 +1,60 @@
+//! Encoding to and decoding from `char` buffers
+//!
+//! Useful for text pipelines that already work in `Vec<char>`, as no
+//! intermediate UTF-8 `String` is built.
+
+use crate::decoder::decode_pairs;
+use crate::{encode_each, Base64Config, DecodeError};
+
+/// Encodes `data` and appends the Base64 characters to `out`.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `out`: The buffer to append to. Existing contents are kept.
+pub fn encode_chars(data: &[u8], out: &mut Vec<char>) {
+    out.reserve(data.len().div_ceil(3) * 4);
+    encode_each(data, |c| out.push(c));
+}
+
+/// Decodes Base64 characters back into binary data.
+///
+/// Parameters:
+/// - `data`: A slice of Base64 characters.
+///
+/// Returns: The decoded bytes, or a `DecodeError` whose `index` counts
+/// `char`s rather than bytes. A non-ASCII character is reported as a
+/// `NonAsciiChar`, like `base64_decode` does for the same text.
+pub fn decode_chars(data: &[char]) -> Result<Vec<u8>, DecodeError> {
+    let bytes = data.iter().enumerate().map(|(index, &c)| {
+        let mut utf8 = [0u8; 4];
+        (index, c.encode_utf8(&mut utf8).as_bytes()[0])
+    });
+    decode_pairs(&Base64Config::STANDARD, bytes).map_err(|err| match err {
+        DecodeError::InvalidByte { index, byte, .. } if !byte.is_ascii() => {
+            DecodeError::non_ascii_char(index, data[index])
+        }
+        err => err,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_appends_and_decodes_back() {
+        let mut out: Vec<char> = "data:".chars().collect();
+        encode_chars(b"Many hands", &mut out);
+        assert_eq!(out.iter().collect::<String>(), "data:TWFueSBoYW5kcw==");
+        assert_eq!(decode_chars(&out[5..]).unwrap(), b"Many hands");
+    }
+
+    #[test]
+    fn decode_reports_char_index() {
+        let input: Vec<char> = "TW\u{e9}u".chars().collect();
+        assert_eq!(
+            decode_chars(&input),
+            Err(DecodeError::non_ascii_char(2, '\u{e9}'))
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! A check character for short, human-typed codes
+
+use crate::decoder::decode_with;
+use crate::{Alphabet, Base64Config, DecodeError};
+
+/// Returns the check character for the standard alphabet characters in
+/// `encoded`: the character of the sum of their sextets, modulo 64.
+fn check_char(encoded: &[u8]) -> u8 {
+    let sum = encoded.iter().fold(0u8, |sum, &b| {
+        sum.wrapping_add(Alphabet::STANDARD.decode_byte(b))
+    });
+    Alphabet::STANDARD.encode_sextet(sum % 64)
+}
+
+/// Encodes `data` with a check character appended, so that typos in a
+/// code typed in by hand are caught when decoding it.
+///
+/// The data is encoded with the standard alphabet and no padding. Then
+/// the sextet values (0-63) of all its characters are added up, and the
+/// character for the sum modulo 64 is appended. Changing any single
+/// character, the check character included, always changes the sum, so
+/// `decode_with_check` rejects it. Swapping two characters does not, and
+/// this is no protection against deliberate tampering.
+///
+/// Example:
+/// ```
+/// use base64::encode_with_check;
+///
+/// // T, W, F and u are 19, 22, 5 and 46, which add up to 92 = 64 + 28.
+/// assert_eq!(encode_with_check(b"Man"), "TWFuc");
+/// ```
+pub fn encode_with_check(data: &[u8]) -> String {
+    let mut encoded = Base64Config::STANDARD_NO_PAD.encode(data);
+    encoded.push(check_char(encoded.as_bytes()) as char);
+    encoded
+}
+
+/// Decodes the output of `encode_with_check`, verifying its check
+/// character.
+///
+/// Returns: The decoded bytes, or:
+/// - `InvalidLength` if `data` is empty, as it lacks a check character.
+/// - Any error decoding the data itself, or `InvalidByte` for a check
+///   character outside the alphabet.
+/// - `ChecksumMismatch` if the data decodes but does not add up to the
+///   check character.
+pub fn decode_with_check(data: &str) -> Result<Vec<u8>, DecodeError> {
+    let Some((&check, encoded)) = data.as_bytes().split_last() else {
+        return Err(DecodeError::InvalidLength { len: 0 });
+    };
+    // `encoded` may end inside a multi-byte character, so errors are
+    // reported against all of `data`.
+    let decoded =
+        decode_with(&Base64Config::STANDARD_NO_PAD, encoded).map_err(|err| err.report_str(data))?;
+    if !Alphabet::STANDARD.contains(check) {
+        return Err(DecodeError::invalid_byte(encoded.len(), check).report_str(data));
+    }
+    if check != check_char(encoded) {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_character_round_trips() {
+        for len in 0..20u8 {
+            let data: Vec<u8> = (0..len).map(|i| i.wrapping_mul(97)).collect();
+            let encoded = encode_with_check(&data);
+            assert_eq!(decode_with_check(&encoded).unwrap(), data);
+        }
+        assert_eq!(encode_with_check(b""), "A");
+    }
+
+    #[test]
+    fn any_single_changed_character_is_caught() {
+        let encoded = encode_with_check(b"ab1-Z9");
+        for i in 0..encoded.len() {
+            for c in ('A'..='Z').chain('a'..='z').chain('0'..='9') {
+                let mut typo = encoded.clone().into_bytes();
+                if typo[i] == c as u8 {
+                    continue;
+                }
+                typo[i] = c as u8;
+                let typo = String::from_utf8(typo).unwrap();
+                assert!(decode_with_check(&typo).is_err(), "{}", typo);
+            }
+        }
+        assert_eq!(
+            decode_with_check("TWFuA"),
+            Err(DecodeError::ChecksumMismatch)
+        );
+        assert_eq!(
+            decode_with_check("TWFu="),
+            Err(DecodeError::invalid_byte(4, b'='))
+        );
+        assert_eq!(
+            decode_with_check(""),
+            Err(DecodeError::InvalidLength { len: 0 })
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Comparing Base64 strings without decoding them into buffers
+
+use crate::decoder::Decoded;
+use crate::{Base64Config, DecodeError, PADDING};
+
+/// Removes well-formed trailing padding from `data`.
+fn unpadded(data: &str) -> Result<&str, DecodeError> {
+    let unpadded = data.trim_end_matches(PADDING);
+    let padding = data.len() - unpadded.len();
+    if padding > 0 && (padding > 2 || !data.len().is_multiple_of(4)) {
+        return Err(DecodeError::InvalidPadding);
+    }
+    Ok(unpadded)
+}
+
+/// Lazily decodes `data` after folding the URL-safe characters onto the
+/// standard ones and removing well-formed trailing padding.
+fn normalized(
+    data: &str,
+) -> Result<Decoded<'static, impl Iterator<Item = (usize, u8)> + '_>, DecodeError> {
+    let unpadded = unpadded(data)?;
+    let bytes = unpadded
+        .bytes()
+        .enumerate()
+        .map(|(index, byte)| match byte {
+            b'-' => (index, b'+'),
+            b'_' => (index, b'/'),
+            other => (index, other),
+        });
+    Ok(Decoded::new(&Base64Config::STANDARD_NO_PAD, bytes))
+}
+
+/// Checks whether two Base64 strings encode the same bytes, regardless of
+/// padding and of whether they use the standard or URL-safe alphabet.
+///
+/// Both strings are decoded side by side without intermediate buffers,
+/// and the comparison stops at the first differing byte. This makes it
+/// cheap to use for deduplication and cache keys.
+///
+/// Parameters:
+/// - `a`, `b`: The Base64 strings to compare.
+///
+/// Returns: Whether both decode to the same bytes, or a `DecodeError` if
+/// one of them is malformed. Errors after the first difference may go
+/// unnoticed, since decoding stops there.
+pub fn semantically_equal(a: &str, b: &str) -> Result<bool, DecodeError> {
+    let mut left = normalized(a).map_err(|err| err.report_str(a))?;
+    let mut right = normalized(b).map_err(|err| err.report_str(b))?;
+    loop {
+        let x = left.next().transpose().map_err(|err| err.report_str(a))?;
+        let y = right.next().transpose().map_err(|err| err.report_str(b))?;
+        match (x, y) {
+            (None, None) => return Ok(true),
+            (Some(x), Some(y)) if x == y => {}
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Checks whether two Base64 strings decode to the same number of bytes,
+/// regardless of padding.
+///
+/// Only the lengths and trailing padding are looked at; the characters
+/// themselves are not decoded or checked.
+///
+/// Parameters:
+/// - `a`, `b`: The Base64 strings to compare.
+///
+/// Returns: Whether both decode to as many bytes, or `InvalidPadding` if
+/// the length or padding of one of them is impossible.
+pub fn decoded_len_eq(a: &str, b: &str) -> Result<bool, DecodeError> {
+    let a_len = decoded_len(a).map_err(|err| err.report_str(a))?;
+    let b_len = decoded_len(b).map_err(|err| err.report_str(b))?;
+    Ok(a_len == b_len)
+}
+
+/// Returns the number of bytes `data` decodes to, judging only by its
+/// length and padding.
+fn decoded_len(data: &str) -> Result<usize, DecodeError> {
+    let unpadded = unpadded(data)?;
+    // A single leftover character can never make up a whole byte.
+    if unpadded.len() % 4 == 1 {
+        return Err(DecodeError::InvalidPadding);
+    }
+    Ok(unpadded.len() * 3 / 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base64_encode, base64_encode_nopad, base64_encode_urlsafe, base64_encode_urlsafe_nopad,
+    };
+
+    #[test]
+    fn all_variants_of_the_same_bytes_are_equal() {
+        for data in [&b""[..], b"\xfb", b"\xfb\xff", b"\xfb\xef\xbe\xff\x00"] {
+            let variants = [
+                base64_encode(data),
+                base64_encode_nopad(data),
+                base64_encode_urlsafe(data),
+                base64_encode_urlsafe_nopad(data),
+            ];
+            for a in &variants {
+                for b in &variants {
+                    assert_eq!(semantically_equal(a, b), Ok(true), "{} vs {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn different_bytes_are_not_equal() {
+        assert_eq!(semantically_equal("Zm9v", "Zm9w"), Ok(false));
+        assert_eq!(semantically_equal("Zm9v", "Zm9vYg"), Ok(false));
+        assert_eq!(semantically_equal("-_8=", "+/A="), Ok(false));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert_eq!(
+            semantically_equal("Zg=", "Zg"),
+            Err(DecodeError::InvalidPadding)
+        );
+        assert_eq!(
+            semantically_equal("Zm9v", "Zm$v"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+    }
+
+    #[test]
+    fn decoded_lengths_are_compared_without_decoding() {
+        for len in 0..10 {
+            let data = vec![0xa5; len];
+            let padded = base64_encode(&data);
+            let unpadded = base64_encode_urlsafe_nopad(&data);
+            assert_eq!(decoded_len_eq(&padded, &unpadded), Ok(true));
+            assert_eq!(decoded_len(&padded), Ok(len));
+            assert_eq!(decoded_len_eq(&padded, &base64_encode(&[0; 11])), Ok(false));
+        }
+        assert_eq!(decoded_len_eq("Zm9v", "$$$$"), Ok(true));
+        assert_eq!(
+            decoded_len_eq("Zm9v", "Zm9vY"),
+            Err(DecodeError::InvalidPadding)
+        );
+        assert_eq!(
+            decoded_len_eq("Zg=", "Zg"),
+            Err(DecodeError::InvalidPadding)
+        );
+    }
+}
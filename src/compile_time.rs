@@ -0,0 +1,228 @@
+//! Decoding Base64 literals at compile time
+
+use crate::{DecodeError, Variant, DECODE_TABLE, INVALID, PADDING};
+
+/// Returns the number of bytes a padded, standard alphabet Base64 string
+/// decodes to, or `None` if its length is not a multiple of 4.
+///
+/// This only looks at the length and trailing padding; the content is
+/// checked by `const_decode`.
+pub const fn const_decoded_len(data: &[u8]) -> Option<usize> {
+    if !data.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut padding = 0;
+    while padding < 2 && padding < data.len() && data[data.len() - 1 - padding] == PADDING as u8 {
+        padding += 1;
+    }
+    Some(data.len() / 4 * 3 - padding)
+}
+
+/// Decodes padded, standard alphabet Base64 into an array of exactly `N`
+/// bytes, usable in `const` contexts.
+///
+/// No allocation takes place, which is what makes this a `const fn`. Use
+/// the `b64_decode!` macro to have `N` worked out from the literal. Input
+/// is checked exactly like `base64_decode_bytes` checks it, and gives the
+/// same errors.
+///
+/// Returns: The decoded bytes, or:
+/// - `InvalidLength` holding the decoded length if the input is valid but
+///   does not decode to exactly `N` bytes.
+/// - `InvalidByte` or `WrongAlphabet` for a byte outside the charset, or
+///   for data after padding.
+/// - `InvalidPadding` for padding that does not stand in for the 3rd or
+///   4th character of a group, and for a last group that is incomplete.
+pub const fn const_decode<const N: usize>(data: &[u8]) -> Result<[u8; N], DecodeError> {
+    let mut output = [0u8; N];
+    let mut written = 0;
+    // Sextets of the current group, and how many there are.
+    let mut buffer = 0u32;
+    let mut sextets = 0;
+    let mut padding = 0;
+
+    let mut index = 0;
+    while index < data.len() {
+        let byte = data[index];
+        let value = DECODE_TABLE[byte as usize];
+        if byte == PADDING as u8 {
+            // Padding can only stand in for the 3rd and 4th character.
+            if sextets < 2 || sextets + padding >= 4 {
+                return Err(DecodeError::InvalidPadding);
+            }
+            padding += 1;
+        } else if value == INVALID || padding > 0 {
+            return Err(match byte {
+                b'-' | b'_' => DecodeError::wrong_alphabet(index, byte, Variant::Standard),
+                _ => DecodeError::invalid_byte(index, byte),
+            });
+        } else {
+            buffer = (buffer << 6) | value as u32;
+            sextets += 1;
+            if sextets == 4 {
+                written = emit(&mut output, written, &buffer.to_be_bytes(), 3);
+                buffer = 0;
+                sextets = 0;
+            }
+        }
+        index += 1;
+    }
+
+    if sextets > 0 {
+        if sextets + padding != 4 {
+            return Err(DecodeError::InvalidPadding);
+        }
+        // Left-align the partial group as if it had been zero filled.
+        let bits = buffer << (6 * (4 - sextets));
+        written = emit(&mut output, written, &bits.to_be_bytes(), sextets - 1);
+    }
+    if written != N {
+        return Err(DecodeError::InvalidLength { len: written });
+    }
+    Ok(output)
+}
+
+/// Stores the first `count` of the 3 bytes of a left-aligned group at
+/// `written`, as far as `output` has room, and returns the new count of
+/// decoded bytes.
+const fn emit<const N: usize>(
+    output: &mut [u8; N],
+    mut written: usize,
+    group: &[u8; 4],
+    count: usize,
+) -> usize {
+    let mut i = 1;
+    while i <= count {
+        if written < N {
+            output[written] = group[i];
+        }
+        written += 1;
+        i += 1;
+    }
+    written
+}
+
+/// Decodes a Base64 string literal into a byte array at compile time.
+///
+/// The array length is derived from the literal, so it has zero runtime
+/// cost and a malformed literal fails the build instead of a test.
+///
+/// Example:
+/// ```
+/// const GREETING: [u8; 5] = base64::b64_decode!("aGVsbG8=");
+/// assert_eq!(&GREETING, b"hello");
+/// ```
+///
+/// An invalid literal is a compile error:
+/// ```compile_fail
+/// const BAD: [u8; 3] = base64::b64_decode!("aGV$");
+/// ```
+///
+/// And so is a target of the wrong length:
+/// ```compile_fail
+/// const SHORT: [u8; 4] = base64::b64_decode!("aGVsbG8=");
+/// ```
+#[macro_export]
+macro_rules! b64_decode {
+    ($encoded:expr) => {{
+        const ENCODED: &[u8] = $encoded.as_bytes();
+        const LEN: usize = match $crate::const_decoded_len(ENCODED) {
+            Some(len) => len,
+            None => panic!("Base64 literal length must be a multiple of 4"),
+        };
+        const DECODED: [u8; LEN] = match $crate::const_decode::<LEN>(ENCODED) {
+            Ok(decoded) => decoded,
+            Err(_) => panic!("invalid Base64 literal"),
+        };
+        DECODED
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_decode_bytes, base64_encode};
+
+    const KEY: [u8; 32] = b64_decode!("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=");
+
+    #[test]
+    fn macro_decodes_at_compile_time() {
+        let expected: Vec<u8> = (0..32).collect();
+        assert_eq!(KEY.to_vec(), expected);
+        assert_eq!(b64_decode!(""), [0u8; 0]);
+    }
+
+    #[test]
+    fn const_decode_matches_runtime_decode() {
+        macro_rules! check_lengths {
+            ($($len:literal),*) => {$(
+                let data: Vec<u8> = (0..$len).map(|i: u8| 0xa5 ^ (i * 13)).collect();
+                let encoded = base64_encode(&data);
+                assert_eq!(const_decoded_len(encoded.as_bytes()), Some($len));
+                assert_eq!(
+                    const_decode::<$len>(encoded.as_bytes()).map(Vec::from),
+                    base64_decode(&encoded)
+                );
+
+                // A foreign byte replacing the first character.
+                let corrupt = encoded.replacen(|_| true, "$", 1);
+                if $len > 0 {
+                    assert_eq!(
+                        const_decode::<$len>(corrupt.as_bytes()).map(Vec::from),
+                        base64_decode(&corrupt)
+                    );
+                }
+            )*};
+        }
+        check_lengths!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12);
+
+        // Whatever `N` is, malformed input gives the runtime error.
+        macro_rules! check_malformed {
+            ($input:expr, $($len:literal),*) => {$(
+                assert_eq!(
+                    const_decode::<$len>($input).map(Vec::from),
+                    base64_decode_bytes($input),
+                    "{:?} into {} bytes",
+                    String::from_utf8_lossy($input),
+                    $len
+                );
+            )*};
+        }
+        for input in [
+            &b"QQ=A"[..],
+            b"=QQ=",
+            b"Q===",
+            b"====",
+            b"QQ==QQ==",
+            b"QUJD=A==",
+            b"=QUJ",
+            b"QU=D",
+            b"QQ=-",
+            b"QQ=",
+            b"QUJ",
+            b"QUJDQ",
+            b"QUJ_",
+        ] {
+            check_malformed!(input, 0, 1, 2, 3, 4, 5);
+        }
+    }
+
+    #[test]
+    fn const_decode_errors() {
+        assert_eq!(const_decoded_len(b"TWF"), None);
+        assert_eq!(const_decode::<2>(b"TWF"), Err(DecodeError::InvalidPadding));
+        assert_eq!(
+            const_decode::<4>(b"TWFu"),
+            Err(DecodeError::InvalidLength { len: 3 })
+        );
+        assert_eq!(
+            const_decode::<3>(b"TW$u"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+        assert_eq!(
+            const_decode::<6>(b"TQ==TWFu"),
+            Err(DecodeError::invalid_byte(4, b'T'))
+        );
+        assert_eq!(const_decode::<1>(b"T==="), Err(DecodeError::InvalidPadding));
+    }
+}
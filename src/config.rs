@@ -0,0 +1,781 @@
+//! Options controlling how Base64 is encoded and decoded
+
+use crate::decoder::decode_with;
+use crate::encoder::encode_with;
+use crate::{Alphabet, DecodeError, PADDING};
+
+/// How the decoder treats `=` padding at the end of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// The final group must be padded to 4 characters.
+    Required,
+    /// Correct padding is accepted, and so is its absence. Padding that is
+    /// present but has the wrong length or position is still rejected.
+    Optional,
+    /// Any `=` is rejected as an invalid byte.
+    Forbidden,
+}
+
+/// What `Base64Config::decode_to_string` does with decoded bytes that are
+/// not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Fail with `DecodeError::NotUtf8`.
+    Error,
+    /// Replace each invalid sequence with U+FFFD, the replacement
+    /// character.
+    Replace,
+}
+
+/// The line terminator written by `Base64Config::trailing_newline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`, as written by GNU `base64` and OpenSSL.
+    Lf,
+    /// `\r\n`, as used by MIME and most Windows tools.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the terminator's characters.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// A set of encoding and decoding options.
+///
+/// `Base64Config::new()` encodes and decodes exactly like `base64_encode`
+/// and `base64_decode`; each builder method switches on an extra check or
+/// tolerance.
+///
+/// The four common variants are available as constants:
+///
+/// | Constant          | Alphabet    | Padding  | Named functions                 |
+/// |-------------------|-------------|----------|---------------------------------|
+/// | `STANDARD`        | `+` and `/` | required | `base64_encode`                 |
+/// | `STANDARD_NO_PAD` | `+` and `/` | none     | `base64_encode_nopad`           |
+/// | `URL_SAFE`        | `-` and `_` | required | `base64_encode_urlsafe`         |
+/// | `URL_SAFE_NO_PAD` | `-` and `_` | none     | `base64_encode_urlsafe_nopad`   |
+///
+/// Each named encode function has a `decode` counterpart.
+///
+/// Example:
+/// ```
+/// use base64::Base64Config;
+///
+/// let config = Base64Config::new().ascii_only(true);
+/// assert_eq!(config.decode(b"TWFu").unwrap(), b"Man");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base64Config {
+    pub(crate) alphabet: Alphabet,
+    pub(crate) pad: bool,
+    pub(crate) pad_char: u8,
+    pub(crate) padding: Padding,
+    pub(crate) ascii_only: bool,
+    pub(crate) strict_charset: bool,
+    pub(crate) exact_capacity: bool,
+    pub(crate) allow_crlf: bool,
+    pub(crate) allow_whitespace: bool,
+    pub(crate) excess_padding: bool,
+    pub(crate) mixed_alphabets: bool,
+    pub(crate) url_escapes: bool,
+    pub(crate) canonical: bool,
+    pub(crate) legacy: bool,
+    pub(crate) invalid_utf8: InvalidUtf8,
+    pub(crate) trailing_newline: Option<LineEnding>,
+}
+
+impl Base64Config {
+    /// Standard alphabet with padding (RFC 4648 §4).
+    pub const STANDARD: Base64Config = Base64Config::new();
+
+    /// Standard alphabet without padding.
+    pub const STANDARD_NO_PAD: Base64Config = Base64Config::new().pad(false);
+
+    /// URL-safe alphabet with padding (RFC 4648 §5).
+    pub const URL_SAFE: Base64Config = Base64Config::new().alphabet(Alphabet::URL_SAFE);
+
+    /// URL-safe alphabet without padding, as used by JWTs.
+    pub const URL_SAFE_NO_PAD: Base64Config = Base64Config::URL_SAFE.pad(false);
+
+    /// Creates the default configuration.
+    pub const fn new() -> Self {
+        Base64Config {
+            alphabet: Alphabet::STANDARD,
+            pad: true,
+            pad_char: PADDING as u8,
+            padding: Padding::Required,
+            ascii_only: false,
+            strict_charset: false,
+            exact_capacity: false,
+            allow_crlf: false,
+            allow_whitespace: false,
+            excess_padding: false,
+            mixed_alphabets: false,
+            url_escapes: false,
+            canonical: false,
+            legacy: false,
+            invalid_utf8: InvalidUtf8::Error,
+            trailing_newline: None,
+        }
+    }
+
+    /// Decodes exactly like `base64_decode` did before padding was checked
+    /// properly, for consumers that still rely on it while migrating.
+    ///
+    /// That decoder accepted `=` anywhere, e.g. `QQ==QUJD` as `AABC`, and
+    /// `=` in the middle of a group just shifted the bits around, turning
+    /// `Q=Q=` into `D`. The one deliberate deviation is padding with no
+    /// data before it, such as `====` or `=QUJD`, which is now rejected
+    /// as `InvalidPadding` instead of decoding to garbage zero bytes.
+    /// Every other decoding option is ignored in this mode; encoding is
+    /// unaffected.
+    #[deprecated(note = "only meant for migrating off the old decoder; use `Base64Config::new`")]
+    pub const fn legacy() -> Self {
+        let mut config = Base64Config::new();
+        config.legacy = true;
+        config
+    }
+
+    /// The strictest decoding, for security-sensitive tokens where only
+    /// the one canonical encoding of the data may be accepted.
+    ///
+    /// On top of the defaults, which already reject whitespace, padding
+    /// before the end and a missing or partial final padding, this turns
+    /// on `strict_charset`, `ascii_only` and `canonical`. Together they
+    /// reject:
+    ///
+    /// | Input              | Error                                    |
+    /// |--------------------|------------------------------------------|
+    /// | `TWE` (no padding) | `InvalidPadding`                         |
+    /// | `TQ=` (too little) | `InvalidPadding`                         |
+    /// | `TQ===` (too much) | `InvalidPadding`                         |
+    /// | `TQ==TWFu`         | `InvalidByte` at the first `T` after `=` |
+    /// | `TW Fu`, `TW\nFu`  | `InvalidByte` at the whitespace          |
+    /// | `TW-u`             | `WrongAlphabet` at the `-`               |
+    /// | `TR==` (bits set)  | `InvalidByte` at the `R`                 |
+    ///
+    /// Non-ASCII bytes are reported as `NonAsciiByte`.
+    pub const fn strict() -> Self {
+        Base64Config::new()
+            .strict_charset(true)
+            .ascii_only(true)
+            .canonical(true)
+    }
+
+    /// The most forgiving decoding, for messy real-world input such as
+    /// pasted tokens, wrapped logs or hand-edited config files; the
+    /// counterpart of `strict`.
+    ///
+    /// It turns on `allow_whitespace`, `excess_padding`, `mixed_alphabets`
+    /// and `url_escapes`, and makes padding `Padding::Optional`, so all of
+    /// these decode to `Many`:
+    ///
+    /// | Input                 | Tolerated                       |
+    /// |-----------------------|---------------------------------|
+    /// | `TWFu eQ`             | whitespace, missing padding     |
+    /// | `TWFu\teQ==\r\n`      | tabs and line breaks            |
+    /// | `TWFueQ====`          | excess padding                  |
+    /// | `TWFueQ%3D%3D`        | URL-escaped padding             |
+    ///
+    /// and `-_` and `+/` decode alike, whichever alphabet is set.
+    /// Encoding is unaffected. It still rejects:
+    ///
+    /// | Input                 | Error                                    |
+    /// |-----------------------|------------------------------------------|
+    /// | `TW$u`, `TWé`         | `InvalidByte` at the foreign byte        |
+    /// | `TQ==TWFu`            | `InvalidByte` at the first `T` after `=` |
+    /// | `TWFuT`, `TWFuT==`    | `InvalidPadding`                         |
+    /// | `=`, `====`           | `InvalidPadding`                         |
+    /// | `TWFu%41`             | `InvalidByte` at the `%`                 |
+    ///
+    /// Silently dropping bytes it does not understand would hide real
+    /// corruption, so it never does.
+    pub const fn lenient() -> Self {
+        Base64Config::new()
+            .padding(Padding::Optional)
+            .allow_whitespace(true)
+            .excess_padding(true)
+            .mixed_alphabets(true)
+            .url_escapes(true)
+    }
+
+    /// Sets the alphabet used for both encoding and decoding.
+    ///
+    /// Panics if the alphabet contains the padding character set with
+    /// `pad_char`.
+    pub const fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        if alphabet.contains(self.pad_char) {
+            panic!("Base64 alphabet must not contain the padding character");
+        }
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Sets whether encoding appends padding and decoding requires it.
+    ///
+    /// With `pad(false)` the encoder never writes `=`, and the decoder
+    /// rejects it while accepting a final group of 2 or 3 characters.
+    /// This sets the decode side to `Padding::Required` or
+    /// `Padding::Forbidden`; call `padding` afterwards to relax it.
+    pub const fn pad(mut self, pad: bool) -> Self {
+        self.pad = pad;
+        self.padding = if pad {
+            Padding::Required
+        } else {
+            Padding::Forbidden
+        };
+        self
+    }
+
+    /// Sets the padding character, `=` by default, for both encoding and
+    /// decoding.
+    ///
+    /// Some older formats pad with `*` instead. Whatever the character, it
+    /// is only accepted at the end of the input, where `=` would be, and
+    /// `=` itself becomes an invalid byte.
+    ///
+    /// Panics if `pad_char` is not ASCII or is part of the alphabet.
+    pub const fn pad_char(mut self, pad_char: char) -> Self {
+        if !pad_char.is_ascii() {
+            panic!("Base64 padding character must be ASCII");
+        }
+        if self.alphabet.contains(pad_char as u8) {
+            panic!("Base64 alphabet must not contain the padding character");
+        }
+        self.pad_char = pad_char as u8;
+        self
+    }
+
+    /// Makes encoding end its output with a line terminator, like the GNU
+    /// `base64` tool does, which many scripts rely on.
+    ///
+    /// The terminator is only written after the last group; this does not
+    /// wrap lines. As with GNU `base64`, empty input still gives empty
+    /// output. Decoding is unaffected, so turn on `allow_crlf` to decode
+    /// such output with the same config.
+    pub const fn trailing_newline(mut self, ending: Option<LineEnding>) -> Self {
+        self.trailing_newline = ending;
+        self
+    }
+
+    /// Returns the character the encoder pads with, if it pads at all.
+    pub(crate) const fn encode_padding(&self) -> Option<char> {
+        if self.pad {
+            Some(self.pad_char as char)
+        } else {
+            None
+        }
+    }
+
+    /// Sets how the decoder treats padding, without affecting encoding.
+    ///
+    /// `Padding::Optional` suits JWTs and URLs, which usually drop the
+    /// padding but may not.
+    pub const fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Reports bytes ≥ 128 as `DecodeError::NonAsciiByte` instead of
+    /// `DecodeError::InvalidByte`.
+    ///
+    /// Such bytes are never valid Base64 either way. This only makes it
+    /// possible to tell raw binary passed by mistake apart from an ASCII
+    /// character that is not in the charset.
+    pub const fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Accepts nothing but the charset and the padding character.
+    ///
+    /// The whole input is checked against the charset before decoding
+    /// starts, so the error always names the first foreign byte, even
+    /// when the input is also malformed earlier on (e.g. bad padding).
+    /// A multi-byte UTF-8 character such as a full-width digit is reported
+    /// at the index of its first byte. This also overrides any option that
+    /// would otherwise tolerate extra characters, giving a fully
+    /// locked-down decode when combined with `ascii_only`.
+    pub const fn strict_charset(mut self, strict_charset: bool) -> Self {
+        self.strict_charset = strict_charset;
+        self
+    }
+
+    /// Rejects input whose final character has bits set that fall outside
+    /// the decoded data.
+    ///
+    /// In a final group of 2 or 3 characters only the top 2 or 4 bits of
+    /// the last character are used; `TQ==`, `TR==` and `TS==` all decode
+    /// to `M`. With this set only the one encoding the encoder produces,
+    /// with those bits zero, is accepted, so data has exactly one accepted
+    /// form. Other encodings are reported as an `InvalidByte` at the last
+    /// data character.
+    pub const fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Skips `\r` and `\n` anywhere in the input when decoding.
+    ///
+    /// This suits formats that are line-wrapped but otherwise clean, such
+    /// as MIME bodies. Unlike a general whitespace tolerance, spaces, tabs
+    /// and every other byte outside the alphabet are still rejected.
+    /// Ignored when `strict_charset` is on.
+    pub const fn allow_crlf(mut self, allow_crlf: bool) -> Self {
+        self.allow_crlf = allow_crlf;
+        self
+    }
+
+    /// Skips ASCII whitespace anywhere in the input when decoding.
+    ///
+    /// Spaces, tabs, line breaks and form feeds are all passed over, as
+    /// in text that was indented, wrapped or pasted. Ignored when
+    /// `strict_charset` is on.
+    pub const fn allow_whitespace(mut self, allow_whitespace: bool) -> Self {
+        self.allow_whitespace = allow_whitespace;
+        self
+    }
+
+    /// Accepts more padding than the final group needs, e.g. `TQ===` or
+    /// `TWFu=`.
+    ///
+    /// The extra padding must still come at the very end; data after it is
+    /// rejected as usual. A final group of a single character is never
+    /// accepted, however it is padded, and neither is padding with no data
+    /// before it.
+    pub const fn excess_padding(mut self, excess_padding: bool) -> Self {
+        self.excess_padding = excess_padding;
+        self
+    }
+
+    /// Decodes the last two characters of either RFC 4648 alphabet, so
+    /// `+` and `-` both mean 62 and `/` and `_` both mean 63.
+    ///
+    /// This suits input from sources that disagree on the alphabet, even
+    /// within one string. It only applies while the alphabet is
+    /// `Alphabet::STANDARD` or `Alphabet::URL_SAFE`, and is ignored when
+    /// `strict_charset` is on.
+    pub const fn mixed_alphabets(mut self, mixed_alphabets: bool) -> Self {
+        self.mixed_alphabets = mixed_alphabets;
+        self
+    }
+
+    /// Accepts the percent escapes that URL encoding applies to Base64.
+    ///
+    /// Tokens copied out of URLs often have `+`, `/` and `=` escaped, so
+    /// this accepts `%2B`, `%2F` and `%3D` in place of them, with either
+    /// upper or lower case hex digits. Any other escape, such as `%41` for
+    /// `A`, is rejected as an `InvalidByte` pointing at its `%`: producers
+    /// never escape alphanumerics, so it indicates double encoding or
+    /// garbage. Ignored when `strict_charset` is on.
+    pub const fn url_escapes(mut self, url_escapes: bool) -> Self {
+        self.url_escapes = url_escapes;
+        self
+    }
+
+    /// Returns whether the decoder should silently pass over `byte`.
+    pub(crate) const fn skips(&self, byte: u8) -> bool {
+        if self.strict_charset {
+            return false;
+        }
+        (self.allow_crlf && (byte == b'\r' || byte == b'\n'))
+            || (self.allow_whitespace && byte.is_ascii_whitespace())
+    }
+
+    /// Sets what `decode_to_string` does with decoded bytes that are not
+    /// valid UTF-8; by default it fails.
+    pub const fn invalid_utf8(mut self, invalid_utf8: InvalidUtf8) -> Self {
+        self.invalid_utf8 = invalid_utf8;
+        self
+    }
+
+    /// Makes `decode` return a `Vec` whose capacity equals its length.
+    ///
+    /// The exact decoded length is worked out from the input length and
+    /// padding before decoding, so no memory is wasted in buffers that are
+    /// kept around for long. The buffer is shrunk afterwards if the
+    /// estimate was off. Leave this off for short-lived results, where the
+    /// default slightly generous allocation is cheaper.
+    pub const fn exact_capacity(mut self, exact_capacity: bool) -> Self {
+        self.exact_capacity = exact_capacity;
+        self
+    }
+
+    /// Encodes `data` using these options.
+    ///
+    /// Parameters:
+    /// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+    ///
+    /// Returns: A Base64 encoded string.
+    pub fn encode(&self, data: &[u8]) -> String {
+        encode_with(self, data)
+    }
+
+    /// Decodes raw Base64 bytes using these options.
+    ///
+    /// Parameters:
+    /// - `data`: The Base64 text as bytes. No UTF-8 validation is done.
+    ///
+    /// Returns: The decoded bytes, or the first `DecodeError` found.
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        decode_with(self, data).map_err(|err| err.report(data))
+    }
+
+    /// Like `decode`, for string input, reporting a non-ASCII character
+    /// whole as `DecodeError::NonAsciiChar`.
+    pub(crate) fn decode_str(&self, data: &str) -> Result<Vec<u8>, DecodeError> {
+        decode_with(self, data.as_bytes()).map_err(|err| err.report_str(data))
+    }
+
+    /// Decodes raw Base64 bytes that encode text.
+    ///
+    /// Parameters:
+    /// - `data`: The Base64 text as bytes.
+    ///
+    /// Returns: The decoded text, or the first `DecodeError` found. Bytes
+    /// that are not valid UTF-8 are handled as chosen with `invalid_utf8`.
+    pub fn decode_to_string(&self, data: &[u8]) -> Result<String, DecodeError> {
+        let decoded = self.decode(data)?;
+        match String::from_utf8(decoded) {
+            Ok(text) => Ok(text),
+            Err(err) => match self.invalid_utf8 {
+                InvalidUtf8::Error => Err(DecodeError::NotUtf8 {
+                    valid_up_to: err.utf8_error().valid_up_to(),
+                }),
+                InvalidUtf8::Replace => Ok(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+            },
+        }
+    }
+}
+
+impl Default for Base64Config {
+    fn default() -> Self {
+        Base64Config::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_only_distinguishes_binary_input() {
+        let input = b"TWFu\xfe\x00";
+        assert_eq!(
+            Base64Config::new().decode(input),
+            Err(DecodeError::invalid_byte(4, 0xfe))
+        );
+        assert_eq!(
+            Base64Config::new().ascii_only(true).decode(input),
+            Err(DecodeError::non_ascii_byte(4, 0xfe))
+        );
+        assert_eq!(
+            Base64Config::new().ascii_only(true).decode(b"TWFu$"),
+            Err(DecodeError::invalid_byte(4, b'$'))
+        );
+    }
+
+    #[test]
+    fn strict_charset_rejects_homoglyphs_first() {
+        // "Q=" followed by a full-width digit one (U+FF11)
+        let input = "Q=\u{ff11}";
+        assert_eq!(
+            Base64Config::new().decode(input.as_bytes()),
+            Err(DecodeError::InvalidPadding)
+        );
+        let strict = Base64Config::new().strict_charset(true).ascii_only(true);
+        assert_eq!(
+            strict.decode(input.as_bytes()),
+            Err(DecodeError::non_ascii_byte(2, 0xef))
+        );
+        assert_eq!(strict.decode(b"TWFuQQ==").unwrap(), b"ManA");
+    }
+
+    #[test]
+    fn padding_policies() {
+        let required = Base64Config::new().padding(Padding::Required);
+        let optional = Base64Config::new().padding(Padding::Optional);
+        let forbidden = Base64Config::new().padding(Padding::Forbidden);
+
+        for padded in ["Zg==", "Zm8="] {
+            let unpadded = padded.trim_end_matches('=');
+            let expected = crate::base64_decode(padded).unwrap();
+
+            assert_eq!(required.decode(padded.as_bytes()).unwrap(), expected);
+            assert_eq!(optional.decode(padded.as_bytes()).unwrap(), expected);
+            assert!(forbidden.decode(padded.as_bytes()).is_err());
+
+            assert!(required.decode(unpadded.as_bytes()).is_err());
+            assert_eq!(optional.decode(unpadded.as_bytes()).unwrap(), expected);
+            assert_eq!(forbidden.decode(unpadded.as_bytes()).unwrap(), expected);
+        }
+
+        for wrongly_padded in ["Zg=", "Zm8==", "Z===", "Zg==Zg==", "Zm9v="] {
+            for config in [&required, &optional, &forbidden] {
+                assert!(config.decode(wrongly_padded.as_bytes()).is_err());
+            }
+        }
+        assert_eq!(
+            forbidden.decode(b"Zg=="),
+            Err(DecodeError::invalid_byte(2, b'='))
+        );
+    }
+
+    #[test]
+    fn exact_capacity_leaves_no_spare_room() {
+        let config = Base64Config::new().exact_capacity(true);
+        for len in 0..20 {
+            let data: Vec<u8> = (0..len).collect();
+            let decoded = config
+                .decode(crate::base64_encode(&data).as_bytes())
+                .unwrap();
+            assert_eq!(decoded, data);
+            assert_eq!(decoded.capacity(), decoded.len());
+        }
+        let unpadded = config.padding(Padding::Optional).decode(b"TWE").unwrap();
+        assert_eq!(unpadded.capacity(), unpadded.len());
+    }
+
+    #[test]
+    fn allow_crlf_skips_only_line_breaks() {
+        let config = Base64Config::new().allow_crlf(true);
+        assert_eq!(
+            config.decode(b"TWFu\r\neSBo\nYW5k\r\ncw==\r\n").unwrap(),
+            b"Many hands"
+        );
+        assert!(Base64Config::new().decode(b"TWFu\r\neSBo").is_err());
+        assert_eq!(
+            config.decode(b"TWFu eSBo"),
+            Err(DecodeError::invalid_byte(4, b' '))
+        );
+        assert!(config.decode(b"TWFu\teSBo").is_err());
+        assert!(config.strict_charset(true).decode(b"TWFu\r\n").is_err());
+    }
+
+    #[test]
+    fn excess_padding_collapses_to_the_canonical_count() {
+        let normalizing = Base64Config::new().excess_padding(true);
+        for (input, expected) in [
+            (&b"QQ===="[..], &b"A"[..]),
+            (b"QQ===", b"A"),
+            (b"QUI===", b"AB"),
+            (b"TWFu==", b"Man"),
+            (b"QQ==", b"A"),
+        ] {
+            assert_eq!(normalizing.decode(input).unwrap(), expected);
+        }
+        assert_eq!(
+            normalizing.decode(b"===="),
+            Err(DecodeError::InvalidPadding)
+        );
+        assert_eq!(
+            Base64Config::strict().decode(b"QQ===="),
+            Err(DecodeError::InvalidPadding)
+        );
+        assert_eq!(
+            Base64Config::new().decode(b"QQ===="),
+            Err(DecodeError::InvalidPadding)
+        );
+        assert_eq!(normalizing.decode(b"Q==="), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    fn lenient_decodes_messy_input() {
+        let messy = b"  TWFu\teS Bo\r\nYW5k\n  cyH7_78h\n  -_%2B/ IQ=== \n";
+        assert!(Base64Config::new().decode(messy).is_err());
+        assert_eq!(
+            Base64Config::lenient().decode(messy).unwrap(),
+            b"Many hands!\xfb\xff\xbf!\xfb\xff\xbf!"
+        );
+
+        let lenient = Base64Config::lenient();
+        assert_eq!(lenient.decode(b"TWFu=").unwrap(), b"Man");
+        assert_eq!(
+            lenient.decode(b"TW$u"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+        assert_eq!(
+            lenient.decode(b"TQ==TWFu"),
+            Err(DecodeError::invalid_byte(4, b'T'))
+        );
+        assert_eq!(lenient.decode(b"TWFuT"), Err(DecodeError::InvalidPadding));
+        assert_eq!(lenient.decode(b"TWFuT=="), Err(DecodeError::InvalidPadding));
+        for padding in ["=", "==", "===", "===="] {
+            assert_eq!(
+                lenient.decode(padding.as_bytes()),
+                Err(DecodeError::InvalidPadding)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error_or_replaced() {
+        let encoded = Base64Config::new().encode(b"caf\xe9!");
+        assert_eq!(
+            Base64Config::new().decode_to_string(encoded.as_bytes()),
+            Err(DecodeError::NotUtf8 { valid_up_to: 3 })
+        );
+        let lossy = Base64Config::new().invalid_utf8(InvalidUtf8::Replace);
+        assert_eq!(
+            lossy.decode_to_string(encoded.as_bytes()).unwrap(),
+            "caf\u{fffd}!"
+        );
+        assert_eq!(lossy.decode_to_string(b"w6k=").unwrap(), "\u{e9}");
+        assert_eq!(
+            Base64Config::new().decode_to_string(b"w6k=").unwrap(),
+            "\u{e9}"
+        );
+    }
+
+    #[test]
+    fn trailing_newline_ends_non_empty_output() {
+        let lf = Base64Config::new().trailing_newline(Some(LineEnding::Lf));
+        assert_eq!(lf.encode(b"Many"), "TWFueQ==\n");
+        let crlf = Base64Config::new().trailing_newline(Some(LineEnding::CrLf));
+        assert_eq!(crlf.encode(b"Man"), "TWFu\r\n");
+        assert_eq!(lf.encode(b""), "");
+        assert_eq!(Base64Config::new().encode(b"Many"), "TWFueQ==");
+
+        let data: Vec<u8> = (0..200u8).collect();
+        let encoded = lf.encode(&data);
+        assert!(encoded.ends_with('\n') && !encoded[..encoded.len() - 1].contains('\n'));
+        assert_eq!(
+            lf.allow_crlf(true).decode(encoded.as_bytes()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn url_escapes_accepts_both_hex_cases() {
+        let config = Base64Config::new().url_escapes(true);
+        assert_eq!(config.decode(b"Zm8%3D").unwrap(), b"fo");
+        assert_eq!(config.decode(b"Zm8%3d").unwrap(), b"fo");
+        assert_eq!(config.decode(b"Zg%3D%3d").unwrap(), b"f");
+        assert_eq!(config.decode(b"%2b%2F%2B%2f").unwrap(), b"\xfb\xff\xbf");
+        assert!(Base64Config::new().decode(b"Zm8%3D").is_err());
+    }
+
+    #[test]
+    fn url_escapes_rejects_other_escapes() {
+        let config = Base64Config::new().url_escapes(true);
+        for input in [&b"Zm8%41"[..], b"Zm8%3", b"Zm8%zz", b"Zm8%"] {
+            assert_eq!(
+                config.decode(input),
+                Err(DecodeError::invalid_byte(3, b'%'))
+            );
+        }
+        assert_eq!(config.decode(b"Z%3D=="), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    fn decodes_like_base64_decode() {
+        for encoded in [
+            "",
+            "QQ==",
+            "QUI=",
+            "QUJD",
+            "TG9uZyBsaXZlIGVhc3RlciBlZ2dzIDop",
+        ] {
+            assert_eq!(
+                Base64Config::new().decode(encoded.as_bytes()),
+                crate::base64_decode(encoded)
+            );
+        }
+        for malformed in ["Q", "QQ", "QQ=", "Q===", "=", "QQ==QUJD"] {
+            assert!(Base64Config::new().decode(malformed.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn star_padding_round_trips() {
+        let config = Base64Config::new().pad_char('*');
+        assert!(!Alphabet::STANDARD.contains(b'*'));
+        for (data, encoded) in [(&b"M"[..], "TQ**"), (b"Ma", "TWE*"), (b"Man", "TWFu")] {
+            assert_eq!(config.encode(data), encoded);
+            assert_eq!(config.decode(encoded.as_bytes()).unwrap(), data);
+        }
+        assert_eq!(
+            config.clone().exact_capacity(true).decode(b"TQ**").unwrap(),
+            b"M"
+        );
+
+        assert_eq!(
+            config.decode(b"TQ=="),
+            Err(DecodeError::invalid_byte(2, b'='))
+        );
+        assert_eq!(
+            config.decode(b"TQ**TWFu"),
+            Err(DecodeError::invalid_byte(4, b'T'))
+        );
+        assert_eq!(config.decode(b"T***"), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    #[should_panic(expected = "padding character")]
+    fn pad_char_must_not_be_in_the_alphabet() {
+        Base64Config::new().pad_char('+');
+    }
+
+    #[test]
+    fn canonical_rejects_stray_trailing_bits() {
+        let config = Base64Config::new().canonical(true);
+        assert_eq!(config.decode(b"TQ==").unwrap(), b"M");
+        assert_eq!(config.decode(b"TWE=").unwrap(), b"Ma");
+        assert_eq!(
+            config.decode(b"TR=="),
+            Err(DecodeError::invalid_byte(1, b'R'))
+        );
+        assert_eq!(
+            config.decode(b"TWF="),
+            Err(DecodeError::invalid_byte(2, b'F'))
+        );
+        assert_eq!(Base64Config::new().decode(b"TR==").unwrap(), b"M");
+    }
+
+    #[test]
+    fn strict_rejects_everything_non_canonical() {
+        let strict = Base64Config::strict();
+        assert_eq!(strict.decode(b"TWFueQ==").unwrap(), b"Many");
+        let cases: [(&[u8], DecodeError); 9] = [
+            (b"TWE", DecodeError::InvalidPadding),
+            (b"TQ=", DecodeError::InvalidPadding),
+            (b"TQ===", DecodeError::InvalidPadding),
+            (b"TQ==TWFu", DecodeError::invalid_byte(4, b'T')),
+            (b"TW Fu", DecodeError::invalid_byte(2, b' ')),
+            (b"TW\nFu", DecodeError::invalid_byte(2, b'\n')),
+            (
+                b"TW-u",
+                DecodeError::wrong_alphabet(2, b'-', crate::Variant::Standard),
+            ),
+            (b"TR==", DecodeError::invalid_byte(1, b'R')),
+            (b"TW\xc3\xa9u", DecodeError::non_ascii_byte(2, 0xc3)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(strict.decode(input), Err(expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn legacy_reproduces_the_old_decoder() {
+        let legacy = Base64Config::legacy();
+        for (input, old) in [
+            (&b"QQ==QUJD"[..], &b"AABC"[..]),
+            (b"QUI=QQ==", b"ABA"),
+            (b"QQ=A", b"A\x00"),
+            (b"Q=Q=", b"D"),
+        ] {
+            assert_eq!(legacy.decode(input).unwrap(), old);
+            assert!(Base64Config::new().decode(input).is_err());
+        }
+        assert_eq!(legacy.decode(b"=QUJD"), Err(DecodeError::InvalidPadding));
+        assert_eq!(legacy.decode(b"===="), Err(DecodeError::InvalidPadding));
+        assert_eq!(legacy.decode(b"TWFueQ==").unwrap(), b"Many");
+    }
+}
@@ -0,0 +1,526 @@
+//! The configurable decoding state machine
+//!
+//! Input is consumed one byte at a time so the same state can be driven
+//! by one-shot, chunked and streaming decoders alike.
+
+use std::hash::Hasher;
+
+use crate::{Alphabet, Base64Config, DecodeError, Padding, INVALID, PADDING};
+
+/// Decoding progress through the current 4 character group.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeState {
+    // Sextets of the current group, most significant first.
+    buffer: u32,
+    // Number of data characters collected in the current group.
+    sextets: u8,
+    // Number of padding characters seen at the end of the input.
+    padding: u8,
+    // A percent escape that is still being read.
+    escape: Option<Escape>,
+    // Index and value of the last data character.
+    last: (usize, u8),
+    // Whether any data character has been read.
+    started: bool,
+}
+
+/// A `%XX` escape, as used in URLs, whose hex digits are being collected.
+#[derive(Debug, Clone, Copy)]
+struct Escape {
+    // Index of the `%` in the input.
+    index: usize,
+    // Number of hex digits read so far.
+    digits: u8,
+    value: u8,
+}
+
+/// The characters `url_escapes` accepts in escaped form.
+const ESCAPABLE: [u8; 3] = [b'+', b'/', PADDING as u8];
+
+impl DecodeState {
+    pub(crate) fn new() -> Self {
+        DecodeState::default()
+    }
+
+    /// Feeds the input byte found at `index`, appending any completed
+    /// bytes to `out`.
+    pub(crate) fn push(
+        &mut self,
+        config: &Base64Config,
+        index: usize,
+        byte: u8,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        if let Some(mut escape) = self.escape.take() {
+            let digit = (byte as char)
+                .to_digit(16)
+                .ok_or_else(|| invalid_byte(config, escape.index, b'%'))?;
+            escape.value = (escape.value << 4) | digit as u8;
+            escape.digits += 1;
+            if escape.digits < 2 {
+                self.escape = Some(escape);
+                return Ok(());
+            }
+            if !ESCAPABLE.contains(&escape.value) {
+                return Err(invalid_byte(config, escape.index, b'%'));
+            }
+            return self.push_char(config, escape.index, escape.value, out);
+        }
+        if byte == b'%' && config.url_escapes && !config.strict_charset {
+            self.escape = Some(Escape {
+                index,
+                digits: 0,
+                value: 0,
+            });
+            return Ok(());
+        }
+        if config.skips(byte) {
+            return Ok(());
+        }
+        self.push_char(config, index, byte, out)
+    }
+
+    /// Feeds one input character, after any escape has been resolved.
+    fn push_char(
+        &mut self,
+        config: &Base64Config,
+        index: usize,
+        byte: u8,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        if byte == config.pad_char && config.padding != Padding::Forbidden {
+            // Padding can only stand in for the 3rd and 4th character.
+            if self.sextets >= 2 && self.padding + self.sextets < 4 {
+                self.padding += 1;
+            } else if config.excess_padding && self.started && self.sextets != 1 {
+                // Not counted, but data after it must still be rejected.
+                // Padding with no data before it pads nothing.
+                self.padding = self.padding.max(1);
+            } else {
+                return Err(DecodeError::InvalidPadding);
+            }
+            return Ok(());
+        }
+
+        let mut value = config.alphabet.decode_byte(byte);
+        if value == INVALID && config.mixed_alphabets && config.alphabet.variant().is_some() {
+            value = match byte {
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                _ => INVALID,
+            };
+        }
+        if value == INVALID || self.padding > 0 {
+            return Err(invalid_byte(config, index, byte));
+        }
+
+        self.buffer = (self.buffer << 6) | value as u32;
+        self.sextets += 1;
+        self.last = (index, byte);
+        self.started = true;
+        if self.sextets == 4 {
+            out.extend_from_slice(&self.buffer.to_be_bytes()[1..]);
+            self.buffer = 0;
+            self.sextets = 0;
+        }
+        Ok(())
+    }
+
+    /// Ends the input, appending the bytes of a final partial group.
+    pub(crate) fn finish(
+        self,
+        config: &Base64Config,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        if let Some(escape) = self.escape {
+            return Err(invalid_byte(config, escape.index, b'%'));
+        }
+        if self.sextets == 0 {
+            return Ok(());
+        }
+        // Padding that is present must always be complete, and a single
+        // leftover sextet can never make up a whole byte.
+        let complete = if self.padding > 0 || config.padding == Padding::Required {
+            self.sextets + self.padding == 4
+        } else {
+            self.sextets >= 2
+        };
+        if !complete {
+            return Err(DecodeError::InvalidPadding);
+        }
+        // The bits of the last sextet that do not make up a whole byte.
+        let unused = self.buffer & ((1 << (6 * self.sextets as u32 % 8)) - 1);
+        if config.canonical && unused != 0 {
+            let (index, byte) = self.last;
+            return Err(invalid_byte(config, index, byte));
+        }
+
+        // Left-align the partial group as if it had been zero filled.
+        let bits = self.buffer << (6 * (4 - self.sextets as u32));
+        out.extend_from_slice(&bits.to_be_bytes()[1..self.sextets as usize]);
+        Ok(())
+    }
+}
+
+/// Builds the error for a byte that cannot appear where it was found.
+///
+/// A character of the other RFC 4648 alphabet gets its own error, as
+/// mixing up the two is by far the most common cause.
+fn invalid_byte(config: &Base64Config, index: usize, byte: u8) -> DecodeError {
+    if byte >= 0x80 && config.ascii_only {
+        return DecodeError::non_ascii_byte(index, byte);
+    }
+    outside_alphabet(&config.alphabet, index, byte)
+}
+
+/// Builds the error for a byte that is not in `alphabet`, telling a
+/// character of the other RFC 4648 alphabet apart like `invalid_byte`.
+pub(crate) fn outside_alphabet(alphabet: &Alphabet, index: usize, byte: u8) -> DecodeError {
+    match alphabet.variant() {
+        Some(expected) if expected.confused_by(byte) => {
+            DecodeError::wrong_alphabet(index, byte, expected)
+        }
+        _ => DecodeError::invalid_byte(index, byte),
+    }
+}
+
+/// Finds the first byte that is neither in the charset nor padding.
+fn check_charset(config: &Base64Config, data: &[u8]) -> Result<(), DecodeError> {
+    match data
+        .iter()
+        .position(|&b| config.alphabet.decode_byte(b) == INVALID && b != config.pad_char)
+    {
+        Some(index) => Err(invalid_byte(config, index, data[index])),
+        None => Ok(()),
+    }
+}
+
+/// Returns the decoded length of well-formed `data` with at most 2
+/// trailing `pad_char`s and nothing else to skip.
+pub(crate) fn exact_decoded_len(data: &[u8], pad_char: u8) -> usize {
+    let padding = data
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|&&b| b == pad_char)
+        .count();
+    (data.len() - padding) * 3 / 4
+}
+
+/// Decodes all of `data` with `config` in one go.
+pub(crate) fn decode_with(config: &Base64Config, data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    if config.legacy {
+        return crate::decode_each(data.iter().copied().enumerate());
+    }
+    if config.strict_charset {
+        check_charset(config, data)?;
+    }
+
+    let mut state = DecodeState::new();
+    let capacity = if config.exact_capacity {
+        exact_decoded_len(data, config.pad_char)
+    } else {
+        data.len() / 4 * 3 + 3
+    };
+    let mut output = Vec::with_capacity(capacity);
+    for (index, &byte) in data.iter().enumerate() {
+        state.push(config, index, byte, &mut output)?;
+    }
+    state.finish(config, &mut output)?;
+
+    if config.exact_capacity {
+        // Only needed when the estimate was off, e.g. for unusual input.
+        output.shrink_to_fit();
+    }
+    Ok(output)
+}
+
+/// Decodes `(index, byte)` pairs, where `index` is the position reported
+/// in errors, so that other input containers can share the decoder.
+pub(crate) fn decode_pairs(
+    config: &Base64Config,
+    input: impl Iterator<Item = (usize, u8)>,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut state = DecodeState::new();
+    let mut output = Vec::with_capacity(input.size_hint().0 / 4 * 3 + 3);
+    for (index, byte) in input {
+        state.push(config, index, byte, &mut output)?;
+    }
+    state.finish(config, &mut output)?;
+    Ok(output)
+}
+
+/// Lazily decodes `(index, byte)` pairs, yielding output bytes one by one.
+///
+/// Input is only consumed as far as needed to produce the next byte, so
+/// callers can stop early without decoding the rest.
+pub(crate) struct Decoded<'c, I> {
+    config: &'c Base64Config,
+    input: I,
+    state: Option<DecodeState>,
+    pending: Vec<u8>,
+    next: usize,
+}
+
+impl<'c, I: Iterator<Item = (usize, u8)>> Decoded<'c, I> {
+    pub(crate) fn new(config: &'c Base64Config, input: I) -> Self {
+        Decoded {
+            config,
+            input,
+            state: Some(DecodeState::new()),
+            pending: Vec::with_capacity(3),
+            next: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = (usize, u8)>> Iterator for Decoded<'_, I> {
+    type Item = Result<u8, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next == self.pending.len() {
+            self.pending.clear();
+            self.next = 0;
+
+            let state = self.state.as_mut()?;
+            let result = match self.input.next() {
+                Some((index, byte)) => state.push(self.config, index, byte, &mut self.pending),
+                None => {
+                    let state = self.state.take()?;
+                    state.finish(self.config, &mut self.pending)
+                }
+            };
+            if let Err(err) = result {
+                // Nothing more can be decoded after an error.
+                self.state = None;
+                return Some(Err(err));
+            }
+        }
+
+        self.next += 1;
+        Some(Ok(self.pending[self.next - 1]))
+    }
+}
+
+/// A decoder that takes its input in pieces.
+///
+/// Groups may be split anywhere between pieces; the sextets of an
+/// incomplete group are carried over to the next `update`. Error indices
+/// count from the start of the first piece.
+///
+/// Example:
+/// ```
+/// use base64::Base64Decoder;
+///
+/// let mut decoder = Base64Decoder::new();
+/// let mut decoded = decoder.update(b"TWF").unwrap();
+/// decoded.extend(decoder.update(b"ueQ==").unwrap());
+/// decoded.extend(decoder.finalize().unwrap());
+/// assert_eq!(decoded, b"Many");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Base64Decoder {
+    config: Base64Config,
+    state: DecodeState,
+    offset: usize,
+}
+
+impl Base64Decoder {
+    /// Creates a decoder with no buffered input.
+    pub fn new() -> Self {
+        Base64Decoder::default()
+    }
+
+    /// Creates a decoder using the options of `config`.
+    pub fn with_config(config: Base64Config) -> Self {
+        Base64Decoder {
+            config,
+            ..Base64Decoder::default()
+        }
+    }
+
+    /// Feeds more input and returns the bytes of every group that is
+    /// now complete.
+    pub fn update(&mut self, chunk: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut out = Vec::with_capacity(chunk.len() / 4 * 3 + 3);
+        self.update_into(chunk, &mut out)?;
+        Ok(out)
+    }
+
+    /// Like `update`, but appends the decoded bytes to `out`.
+    pub(crate) fn update_into(
+        &mut self,
+        chunk: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        for (i, &byte) in chunk.iter().enumerate() {
+            self.state.push(&self.config, self.offset + i, byte, out)?;
+        }
+        self.offset += chunk.len();
+        Ok(())
+    }
+
+    /// Ends the input and returns the bytes of the final group, if any.
+    pub fn finalize(self) -> Result<Vec<u8>, DecodeError> {
+        let mut out = Vec::with_capacity(2);
+        self.state.finish(&self.config, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// Decodes Base64 that arrives as a sequence of string fragments, such as
+/// the lines of a wrapped file, without joining them first.
+///
+/// Fragments may split groups at any position.
+///
+/// Parameters:
+/// - `chunks`: The fragments, in order.
+///
+/// Returns: The decoded bytes, or a `DecodeError` whose index counts from
+/// the start of the first fragment.
+pub fn decode_chunks<'a, I: IntoIterator<Item = &'a str>>(
+    chunks: I,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut decoder = Base64Decoder::new();
+    let mut out = Vec::new();
+    for chunk in chunks {
+        let offset = decoder.offset;
+        decoder
+            .update_into(chunk.as_bytes(), &mut out)
+            .map_err(|err| err.in_str_at(chunk, offset))?;
+    }
+    decoder.state.finish(&decoder.config, &mut out)?;
+    Ok(out)
+}
+
+/// How many input bytes `decode_hashing` decodes before hashing their
+/// output, so that it is hashed while still in cache.
+const HASH_BLOCK: usize = 4096;
+
+/// Decodes `data` like `base64_decode`, feeding the decoded bytes to
+/// `hasher` as they are produced, e.g. for content-addressed storage.
+///
+/// The bytes are passed to `Hasher::write` in pieces, so the resulting
+/// state matches a single `write` of all decoded bytes for any hasher
+/// that does not depend on how its input is split, as is the case for
+/// `DefaultHasher`. On error the hasher has seen part of the output.
+///
+/// Example:
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+/// use base64::decode_hashing;
+///
+/// let mut hasher = DefaultHasher::new();
+/// let decoded = decode_hashing("TWFueQ==", &mut hasher).unwrap();
+/// let mut expected = DefaultHasher::new();
+/// expected.write(&decoded);
+/// assert_eq!(hasher.finish(), expected.finish());
+/// ```
+///
+/// Returns: The decoded bytes, or the first `DecodeError` found.
+pub fn decode_hashing<H: Hasher>(data: &str, hasher: &mut H) -> Result<Vec<u8>, DecodeError> {
+    let config = Base64Config::STANDARD;
+    let input = data.as_bytes();
+    let mut state = DecodeState::new();
+    let mut output = Vec::with_capacity(input.len() / 4 * 3 + 3);
+    let mut hashed = 0;
+    for (block, chunk) in input.chunks(HASH_BLOCK).enumerate() {
+        for (offset, &byte) in chunk.iter().enumerate() {
+            state
+                .push(&config, block * HASH_BLOCK + offset, byte, &mut output)
+                .map_err(|err| err.report_str(data))?;
+        }
+        hasher.write(&output[hashed..]);
+        hashed = output.len();
+    }
+    state
+        .finish(&config, &mut output)
+        .map_err(|err| err.report_str(data))?;
+    hasher.write(&output[hashed..]);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_encode;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn fragments_split_anywhere_decode_the_same() {
+        let data: Vec<u8> = (0..200u8).collect();
+        let encoded = base64_encode(&data);
+        for first in 0..encoded.len() {
+            for second in [first, (first + 5).min(encoded.len()), encoded.len()] {
+                let pieces = [
+                    &encoded[..first],
+                    &encoded[first..second],
+                    &encoded[second..],
+                ];
+                assert_eq!(decode_chunks(pieces).unwrap(), data);
+            }
+        }
+        assert_eq!(decode_chunks(Vec::<&str>::new()).unwrap(), b"");
+    }
+
+    #[test]
+    fn fragment_errors_use_global_index() {
+        assert_eq!(
+            decode_chunks(["TWFu", "eSBo", "YW!k"]),
+            Err(DecodeError::invalid_byte(10, b'!'))
+        );
+        assert_eq!(
+            decode_chunks(["TWFu", "eQ"]),
+            Err(DecodeError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn padded_tails_add_no_zero_bytes() {
+        let cases: [(&str, &[u8]); 6] = [
+            ("QQ==", b"A"),
+            ("QUI=", b"AB"),
+            ("AA==", &[0]),
+            ("AAA=", &[0, 0]),
+            ("TWFuQQ==", b"ManA"),
+            ("TWFuAAA=", b"Man\0\0"),
+        ];
+        let unpadded = Base64Config::new().padding(Padding::Optional);
+        for (encoded, expected) in cases {
+            assert_eq!(
+                Base64Config::STANDARD.decode(encoded.as_bytes()).unwrap(),
+                expected
+            );
+            assert_eq!(
+                unpadded
+                    .decode(encoded.trim_end_matches('=').as_bytes())
+                    .unwrap(),
+                expected
+            );
+            let (head, tail) = encoded.split_at(encoded.len() - 1);
+            assert_eq!(decode_chunks([head, tail]).unwrap(), expected);
+            assert_eq!(crate::decode_strict_aligned(encoded).unwrap(), expected);
+            assert_eq!(crate::decode_simd(encoded).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn hashing_decode_matches_hashing_afterwards() {
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i * 31 % 256) as u8).collect();
+        for len in [0, 1, 2, 3, 3071, 3072, 3073, data.len()] {
+            let encoded = base64_encode(&data[..len]);
+            let mut hasher = DefaultHasher::new();
+            let decoded = decode_hashing(&encoded, &mut hasher).unwrap();
+            assert_eq!(decoded, &data[..len]);
+
+            let mut expected = DefaultHasher::new();
+            expected.write(&decoded);
+            assert_eq!(hasher.finish(), expected.finish());
+        }
+        assert_eq!(
+            decode_hashing("TW$u", &mut DefaultHasher::new()),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+    }
+}
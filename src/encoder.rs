@@ -0,0 +1,277 @@
+//! Incremental Base64 encoding
+
+use crate::{Alphabet, Base64Config};
+
+/// Encodes 3 bytes into 4 Base64 characters, appending them to `out`.
+pub(crate) fn encode_group(alphabet: &Alphabet, group: [u8; 3], out: &mut String) {
+    let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+    for shift in [18, 12, 6, 0] {
+        out.push(alphabet.encode_sextet((bits >> shift) as u8) as char);
+    }
+}
+
+/// Encodes a final group of 1 or 2 bytes, padding it to 4 characters
+/// with `pad` if given.
+pub(crate) fn encode_tail(alphabet: &Alphabet, tail: &[u8], pad: Option<char>, out: &mut String) {
+    if tail.is_empty() {
+        return;
+    }
+    let mut group = [0u8; 3];
+    group[..tail.len()].copy_from_slice(tail);
+
+    let mut encoded = String::with_capacity(4);
+    encode_group(alphabet, group, &mut encoded);
+    out.push_str(&encoded[..tail.len() + 1]);
+    if let Some(pad) = pad {
+        for _ in tail.len()..3 {
+            out.push(pad);
+        }
+    }
+}
+
+/// Encodes all of `data` with `config` in one go.
+pub(crate) fn encode_with(config: &Base64Config, data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    let mut groups = data.chunks_exact(3);
+    for group in &mut groups {
+        encode_group(&config.alphabet, [group[0], group[1], group[2]], &mut out);
+    }
+    encode_tail(
+        &config.alphabet,
+        groups.remainder(),
+        config.encode_padding(),
+        &mut out,
+    );
+    // Strict RFC 4648 §4 consumers reject unpadded output, so catch any
+    // slip in the padding computation during development.
+    debug_assert!(
+        !config.pad || out.len().is_multiple_of(4),
+        "padded output of {} characters",
+        out.len()
+    );
+    match config.trailing_newline {
+        Some(ending) if !data.is_empty() => out.push_str(ending.as_str()),
+        _ => {}
+    }
+    out
+}
+
+/// Encodes the concatenation of `sources` as one input, without first
+/// copying them into a single buffer.
+///
+/// Groups of 3 bytes may span two sources, so the result is generally
+/// not the concatenation of the sources' own encodings. The output is
+/// allocated once, sized for the total length.
+///
+/// Example:
+/// ```
+/// use base64::{base64_encode, encode_chain};
+///
+/// assert_eq!(encode_chain(&[b"Ma", b"ny"]), "TWFueQ==");
+/// assert_ne!(encode_chain(&[b"Ma", b"ny"]), base64_encode(b"Ma") + &base64_encode(b"ny"));
+/// ```
+pub fn encode_chain(sources: &[&[u8]]) -> String {
+    let total: usize = sources.iter().map(|source| source.len()).sum();
+    let mut out = String::with_capacity(total.div_ceil(3) * 4);
+    let mut encoder = Base64Encoder::new();
+    for source in sources {
+        encoder.update_into(source, &mut out);
+    }
+    encoder.finalize_into(&mut out);
+    out
+}
+
+/// An encoder that takes its input in pieces.
+///
+/// Output is produced as early as possible: every time 3 input bytes
+/// have accumulated, their 4 characters are returned by the very `update`
+/// call that completed them. At most 2 trailing bytes are ever held back,
+/// waiting for more input or for `finalize` to pad them. This keeps the
+/// latency of streaming the encoded data (e.g. over a socket) to at most
+/// one group.
+///
+/// There is therefore nothing to flush mid-stream: the output of every
+/// `update` can be sent right away, and only `finalize` ever pads.
+///
+/// Example:
+/// ```
+/// use base64::Base64Encoder;
+///
+/// let mut encoder = Base64Encoder::new();
+/// let mut encoded = encoder.update(b"Ma");
+/// assert_eq!(encoded, "");
+/// encoded += &encoder.update(b"ny");
+/// assert_eq!(encoded, "TWFu");
+/// encoded += &encoder.finalize();
+/// assert_eq!(encoded, "TWFueQ==");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Base64Encoder {
+    config: Base64Config,
+    pending: [u8; 2],
+    pending_len: usize,
+    // Whether any input has been fed, for the trailing newline.
+    started: bool,
+}
+
+impl Base64Encoder {
+    /// Creates an encoder with no buffered input.
+    pub fn new() -> Self {
+        Base64Encoder::default()
+    }
+
+    /// Creates an encoder using the alphabet and padding of `config`.
+    pub fn with_config(config: Base64Config) -> Self {
+        Base64Encoder {
+            config,
+            ..Base64Encoder::default()
+        }
+    }
+
+    /// Feeds more input and returns the characters for every group that
+    /// is now complete.
+    ///
+    /// Parameters:
+    /// - `data`: The next piece of input.
+    ///
+    /// Returns: The encoded complete groups, possibly empty.
+    pub fn update(&mut self, data: &[u8]) -> String {
+        let mut out = String::with_capacity((self.pending_len + data.len()) / 3 * 4);
+        self.update_into(data, &mut out);
+        out
+    }
+
+    /// Like `update`, but appends the encoded groups to `out`.
+    pub(crate) fn update_into(&mut self, data: &[u8], out: &mut String) {
+        let mut data = data;
+        self.started |= !data.is_empty();
+
+        if self.pending_len > 0 {
+            let needed = 3 - self.pending_len;
+            if data.len() < needed {
+                self.pending[self.pending_len..self.pending_len + data.len()].copy_from_slice(data);
+                self.pending_len += data.len();
+                return;
+            }
+            let mut group = [0u8; 3];
+            group[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+            group[self.pending_len..].copy_from_slice(&data[..needed]);
+            encode_group(&self.config.alphabet, group, out);
+            data = &data[needed..];
+            self.pending_len = 0;
+        }
+
+        let mut groups = data.chunks_exact(3);
+        for group in &mut groups {
+            encode_group(&self.config.alphabet, [group[0], group[1], group[2]], out);
+        }
+        let rest = groups.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    /// Ends the input and returns the padded final group, if any.
+    pub fn finalize(self) -> String {
+        let mut out = String::new();
+        self.finalize_into(&mut out);
+        out
+    }
+
+    /// Like `finalize`, but appends the final group to `out`.
+    pub(crate) fn finalize_into(self, out: &mut String) {
+        encode_tail(
+            &self.config.alphabet,
+            &self.pending[..self.pending_len],
+            self.config.encode_padding(),
+            out,
+        );
+        match self.config.trailing_newline {
+            Some(ending) if self.started => out.push_str(ending.as_str()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_encode;
+
+    #[test]
+    fn emits_each_group_as_soon_as_it_completes() {
+        let data = b"The quick brown fox";
+        let expected = base64_encode(data);
+        let mut encoder = Base64Encoder::new();
+        let mut encoded = String::new();
+
+        for (i, &byte) in data.iter().enumerate() {
+            let out = encoder.update(&[byte]);
+            if (i + 1) % 3 == 0 {
+                assert_eq!(out, expected[(i + 1) / 3 * 4 - 4..(i + 1) / 3 * 4]);
+            } else {
+                assert_eq!(out, "");
+            }
+            encoded += &out;
+        }
+
+        let tail = encoder.finalize();
+        assert_eq!(tail, "eA==");
+        encoded += &tail;
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn uneven_pieces_match_one_shot_encode() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for piece in 1..8 {
+            let mut encoder = Base64Encoder::new();
+            let mut encoded: String = data.chunks(piece).map(|c| encoder.update(c)).collect();
+            encoded += &encoder.finalize();
+            assert_eq!(encoded, base64_encode(&data));
+        }
+        assert_eq!(Base64Encoder::new().finalize(), "");
+    }
+
+    #[test]
+    fn chain_encodes_the_concatenation() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for first in [0, 1, 2, 5, 100] {
+            for second in [first, first + 1, first + 4, 200] {
+                let sources = [&data[..first], &data[first..second], &data[second..]];
+                assert_eq!(encode_chain(&sources), base64_encode(&data));
+            }
+        }
+        assert_eq!(encode_chain(&[]), "");
+        assert_eq!(encode_chain(&[b"", b""]), "");
+    }
+
+    #[test]
+    fn padded_output_is_whole_groups() {
+        let data = [0x5a; 300];
+        for len in 0..data.len() {
+            for config in [Base64Config::STANDARD, Base64Config::URL_SAFE] {
+                let encoded = config.encode(&data[..len]);
+                assert_eq!(encoded.len(), len.div_ceil(3) * 4);
+            }
+        }
+    }
+
+    #[test]
+    fn encoder_writes_the_trailing_newline_once() {
+        let config = Base64Config::new().trailing_newline(Some(crate::LineEnding::Lf));
+        let mut encoder = Base64Encoder::with_config(config.clone());
+        let mut encoded = encoder.update(b"Many ");
+        encoded += &encoder.update(b"hands");
+        encoded += &encoder.finalize();
+        assert_eq!(encoded, config.encode(b"Many hands"));
+        assert_eq!(Base64Encoder::with_config(config).finalize(), "");
+    }
+
+    #[test]
+    fn encoder_uses_config_alphabet_and_padding() {
+        let mut encoder = Base64Encoder::with_config(Base64Config::URL_SAFE_NO_PAD);
+        let mut encoded = encoder.update(b"\xfb\xff\xbf\xfe");
+        encoded += &encoder.finalize();
+        assert_eq!(encoded, "-_-__g");
+    }
+}
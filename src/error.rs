@@ -0,0 +1,602 @@
+//! Errors that can occur while decoding Base64
+
+use std::error::Error;
+use std::fmt;
+
+use crate::Variant;
+
+/// The reason a Base64 string could not be decoded.
+///
+/// Variants that point at a specific input byte carry its `index`,
+/// counted in bytes from the start of the input. With the `error-context`
+/// feature they also carry a snippet of the input around that byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// A byte that is neither in the charset nor padding was found.
+    InvalidByte {
+        index: usize,
+        byte: u8,
+        #[cfg(feature = "error-context")]
+        context: ErrorContext,
+    },
+    /// A byte ≥ 128 was found while ASCII-only input was required.
+    NonAsciiByte {
+        index: usize,
+        byte: u8,
+        #[cfg(feature = "error-context")]
+        context: ErrorContext,
+    },
+    /// A non-ASCII character was found in string input. `index` is where
+    /// its UTF-8 encoding starts.
+    NonAsciiChar {
+        index: usize,
+        ch: char,
+        #[cfg(feature = "error-context")]
+        context: ErrorContext,
+    },
+    /// A character of the other RFC 4648 alphabet was found, e.g. `+` in
+    /// input decoded as URL-safe.
+    WrongAlphabet {
+        index: usize,
+        byte: u8,
+        expected: Variant,
+        #[cfg(feature = "error-context")]
+        context: ErrorContext,
+    },
+    /// The input ended with an incomplete group or malformed padding.
+    InvalidPadding,
+    /// The input, or the data decoded from it, has a length the decoder
+    /// that was used cannot accept.
+    InvalidLength { len: usize },
+    /// The input did not start with the expected prefix.
+    MissingPrefix,
+    /// The decoded data did not start with the expected magic bytes.
+    BadMagic,
+    /// The decoded data does not have the length it was expected to have.
+    LengthMismatch { expected: usize, actual: usize },
+    /// A decoded byte was requested past the end of the decoded data.
+    IndexOutOfRange { index: usize, len: usize },
+    /// The check character of the input does not match its data.
+    ChecksumMismatch,
+    /// The input does not end with the expected checksum line.
+    MissingChecksum,
+    /// The authentication tag of the input does not match its data, so
+    /// it was tampered with or made with another key.
+    AuthFailed,
+    /// The decoded data was expected to be text but is not valid UTF-8;
+    /// the first `valid_up_to` bytes are.
+    NotUtf8 { valid_up_to: usize },
+}
+
+/// The input around a decoding failure, as shown by `DecodeError::context`.
+///
+/// Only filled in by decoders that see the whole input at once, the same
+/// ones that emit `tracing` events; it is empty otherwise. Contexts
+/// always compare equal, so errors compare by kind and position only.
+///
+/// The snippet is taken from the input bytes, escaped as by
+/// `std::ascii::escape_default`, e.g. `TW[\xff]u`.
+///
+/// The snippet is stored inline rather than in a `String` so that errors
+/// stay free of destructors and usable from `const fn`s.
+#[cfg(feature = "error-context")]
+#[derive(Clone)]
+pub struct ErrorContext {
+    bytes: [u8; CONTEXT_CAPACITY],
+    len: u8,
+}
+
+/// Room for the two sides, a bracketed character of up to 4 bytes escaped
+/// to at most 4 each, and the brackets and ellipses.
+#[cfg(feature = "error-context")]
+const CONTEXT_CAPACITY: usize = 2 * SIDE_CAPACITY + 16 + 8;
+
+/// How much escaped text either side of the bracketed character may use:
+/// 7 escaped bytes, or all of `CONTEXT_RADIUS` printable ones.
+#[cfg(feature = "error-context")]
+const SIDE_CAPACITY: usize = 28;
+
+#[cfg(feature = "error-context")]
+impl ErrorContext {
+    const EMPTY: ErrorContext = ErrorContext {
+        bytes: [0; CONTEXT_CAPACITY],
+        len: 0,
+    };
+
+    /// Brackets the `width` bytes of `input` at `index`, with up to
+    /// `CONTEXT_RADIUS` bytes on either side.
+    fn new(input: &[u8], index: usize, width: usize) -> Self {
+        let end = (index + width).min(input.len());
+        let start = index - ErrorContext::side_len(input[..index].iter().rev());
+        let after = end + ErrorContext::side_len(input[end..].iter());
+
+        let mut context = ErrorContext::EMPTY;
+        if start > 0 {
+            context.push(b"...");
+        }
+        context.push_escaped(&input[start..index]);
+        context.push(b"[");
+        context.push_escaped(&input[index..end]);
+        context.push(b"]");
+        context.push_escaped(&input[end..after]);
+        if after < input.len() {
+            context.push(b"...");
+        }
+        context
+    }
+
+    /// Returns how many of `bytes`, nearest the failure first, go into one
+    /// side of the snippet.
+    fn side_len<'a>(bytes: impl Iterator<Item = &'a u8>) -> usize {
+        let mut room = SIDE_CAPACITY;
+        bytes
+            .take(CONTEXT_RADIUS)
+            .take_while(
+                |&&byte| match room.checked_sub(std::ascii::escape_default(byte).len()) {
+                    Some(left) => {
+                        room = left;
+                        true
+                    }
+                    None => false,
+                },
+            )
+            .count()
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let len = self.len as usize;
+        self.bytes[len..len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len() as u8;
+    }
+
+    fn push_escaped(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            for escaped in std::ascii::escape_default(byte) {
+                self.push(&[escaped]);
+            }
+        }
+    }
+
+    /// Returns the snippet, e.g. `...TG9u[$]Zw==`.
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "error-context")]
+impl fmt::Debug for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ErrorContext").field(&self.as_str()).finish()
+    }
+}
+
+#[cfg(feature = "error-context")]
+impl PartialEq for ErrorContext {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "error-context")]
+impl Eq for ErrorContext {}
+
+/// How many characters around the failure `DecodeError::context` shows.
+const CONTEXT_RADIUS: usize = 8;
+
+impl DecodeError {
+    /// Creates an `InvalidByte` error without context.
+    pub(crate) const fn invalid_byte(index: usize, byte: u8) -> Self {
+        DecodeError::InvalidByte {
+            index,
+            byte,
+            #[cfg(feature = "error-context")]
+            context: ErrorContext::EMPTY,
+        }
+    }
+
+    /// Creates a `NonAsciiByte` error without context.
+    pub(crate) const fn non_ascii_byte(index: usize, byte: u8) -> Self {
+        DecodeError::NonAsciiByte {
+            index,
+            byte,
+            #[cfg(feature = "error-context")]
+            context: ErrorContext::EMPTY,
+        }
+    }
+
+    /// Creates a `NonAsciiChar` error without context.
+    pub(crate) const fn non_ascii_char(index: usize, ch: char) -> Self {
+        DecodeError::NonAsciiChar {
+            index,
+            ch,
+            #[cfg(feature = "error-context")]
+            context: ErrorContext::EMPTY,
+        }
+    }
+
+    /// Creates a `WrongAlphabet` error without context.
+    pub(crate) const fn wrong_alphabet(index: usize, byte: u8, expected: Variant) -> Self {
+        DecodeError::WrongAlphabet {
+            index,
+            byte,
+            expected,
+            #[cfg(feature = "error-context")]
+            context: ErrorContext::EMPTY,
+        }
+    }
+
+    /// Reports a byte ≥ 128 of the string `input` as the whole
+    /// `NonAsciiChar` it belongs to, which is what the caller typed. The
+    /// index moves back to the start of the character if needed.
+    pub(crate) fn in_str(self, input: &str) -> Self {
+        self.in_str_at(input, 0)
+    }
+
+    /// Like `in_str`, for an error whose index counts from `offset` bytes
+    /// before `input`, e.g. from the start of an earlier chunk.
+    pub(crate) fn in_str_at(self, input: &str, offset: usize) -> Self {
+        match self {
+            DecodeError::InvalidByte { index, byte, .. }
+            | DecodeError::NonAsciiByte { index, byte, .. }
+                if !byte.is_ascii() && (offset..offset + input.len()).contains(&index) =>
+            {
+                let start = (0..=index - offset)
+                    .rev()
+                    .find(|&i| input.is_char_boundary(i))
+                    .unwrap_or(0);
+                match input[start..].chars().next() {
+                    Some(ch) => DecodeError::non_ascii_char(offset + start, ch),
+                    None => self,
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// Finishes an error leaving a decoder that saw the whole `input` at
+    /// once: attaches a snippet of the input with the `error-context`
+    /// feature, and emits it as an event with `tracing`.
+    #[cfg_attr(not(feature = "error-context"), allow(unused_variables))]
+    pub(crate) fn report(self, input: &[u8]) -> Self {
+        #[cfg(feature = "error-context")]
+        let err = self.with_context(input);
+        #[cfg(not(feature = "error-context"))]
+        let err = self;
+        #[cfg(feature = "tracing")]
+        crate::trace::decode_failed(&err);
+        err
+    }
+
+    /// Like `report`, for decoders of string input: a non-ASCII character
+    /// is reported whole, as `NonAsciiChar`.
+    pub(crate) fn report_str(self, input: &str) -> Self {
+        self.in_str(input).report(input.as_bytes())
+    }
+
+    /// Attaches a snippet of the decoded `input`.
+    #[cfg(feature = "error-context")]
+    fn with_context(mut self, input: &[u8]) -> Self {
+        let width = match self {
+            DecodeError::NonAsciiChar { ch, .. } => ch.len_utf8(),
+            _ => 1,
+        };
+        if let (
+            Some(index),
+            DecodeError::InvalidByte { context, .. }
+            | DecodeError::NonAsciiByte { context, .. }
+            | DecodeError::NonAsciiChar { context, .. }
+            | DecodeError::WrongAlphabet { context, .. },
+        ) = (self.index().filter(|&i| i < input.len()), &mut self)
+        {
+            *context = ErrorContext::new(input, index, width);
+        }
+        self
+    }
+
+    /// Moves the index of the error `offset` bytes further into the input,
+    /// for errors found in a part of it.
+    pub(crate) fn shifted(mut self, offset: usize) -> Self {
+        if let DecodeError::InvalidByte { index, .. }
+        | DecodeError::NonAsciiByte { index, .. }
+        | DecodeError::NonAsciiChar { index, .. }
+        | DecodeError::WrongAlphabet { index, .. } = &mut self
+        {
+            *index += offset;
+        }
+        self
+    }
+
+    /// Returns the input index the error points at, if it has one.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            DecodeError::InvalidByte { index, .. }
+            | DecodeError::NonAsciiByte { index, .. }
+            | DecodeError::NonAsciiChar { index, .. }
+            | DecodeError::WrongAlphabet { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Describes the error together with a snippet of `input` around it.
+    ///
+    /// The offending character is wrapped in brackets with up to 8
+    /// characters on either side, e.g. `...TG9u[$]Zw==`, which makes
+    /// failures in long strings much easier to track down. `input` should
+    /// be the string that was decoded; errors without an index are
+    /// described without a snippet.
+    ///
+    /// Example:
+    /// ```
+    /// use base64::base64_decode;
+    ///
+    /// let input = "TG9uZyBsaXZlIGVh$3RlciBlZ2dzIDop";
+    /// let err = base64_decode(input).unwrap_err();
+    /// assert!(err.context(input).ends_with("...aXZlIGVh[$]3RlciBlZ..."));
+    /// ```
+    pub fn context(&self, input: &str) -> String {
+        match self.snippet(input) {
+            Some(snippet) => format!("{}: {}", self, snippet),
+            None => self.to_string(),
+        }
+    }
+
+    /// Returns the bracketed snippet of `input` for indexed errors.
+    fn snippet(&self, input: &str) -> Option<String> {
+        let index = self.index().filter(|&i| i < input.len())?;
+
+        // The index may point into a multi-byte character; use all of it.
+        let start = (0..=index)
+            .rev()
+            .find(|&i| input.is_char_boundary(i))
+            .unwrap_or(0);
+        let end = (index + 1..=input.len())
+            .find(|&i| input.is_char_boundary(i))
+            .unwrap_or(input.len());
+
+        let before: Vec<char> = input[..start]
+            .chars()
+            .rev()
+            .take(CONTEXT_RADIUS + 1)
+            .collect();
+        let after: Vec<char> = input[end..].chars().take(CONTEXT_RADIUS + 1).collect();
+
+        let mut snippet = String::new();
+        if before.len() > CONTEXT_RADIUS {
+            snippet.push_str("...");
+        }
+        snippet.extend(before.iter().take(CONTEXT_RADIUS).rev());
+        snippet.push('[');
+        snippet.push_str(&input[start..end]);
+        snippet.push(']');
+        snippet.extend(after.iter().take(CONTEXT_RADIUS));
+        if after.len() > CONTEXT_RADIUS {
+            snippet.push_str("...");
+        }
+        Some(snippet)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidByte { index, byte, .. } => write!(
+                f,
+                "Failed to decode base64: Expected byte from charset, found invalid byte {:#04x} at index {}.",
+                byte, index
+            ),
+            DecodeError::NonAsciiByte { index, byte, .. } => write!(
+                f,
+                "Failed to decode base64: Expected ASCII input, found byte {:#04x} at index {}.",
+                byte, index
+            ),
+            DecodeError::NonAsciiChar { index, ch, .. } => write!(
+                f,
+                "Failed to decode base64: Expected byte from charset, found non-ASCII character '{}' (U+{:04X}) at index {}.",
+                ch, *ch as u32, index
+            ),
+            DecodeError::WrongAlphabet {
+                index,
+                byte,
+                expected,
+                ..
+            } => write!(
+                f,
+                "Failed to decode base64: Expected {} alphabet, found byte {:#04x} ('{}') at index {}.",
+                expected, byte, *byte as char, index
+            ),
+            DecodeError::InvalidPadding => write!(f, "Failed to decode base64: Invalid padding."),
+            DecodeError::InvalidLength { len } => {
+                write!(f, "Failed to decode base64: Invalid length {}.", len)
+            }
+            DecodeError::MissingPrefix => {
+                write!(f, "Failed to decode base64: Expected prefix not found.")
+            }
+            DecodeError::BadMagic => {
+                write!(f, "Failed to decode base64: Expected magic bytes not found.")
+            }
+            DecodeError::LengthMismatch { expected, actual } => write!(
+                f,
+                "Failed to decode base64: Expected {} decoded bytes, found {}.",
+                expected, actual
+            ),
+            DecodeError::IndexOutOfRange { index, len } => write!(
+                f,
+                "Failed to decode base64: Byte index {} is out of range for {} decoded bytes.",
+                index, len
+            ),
+            DecodeError::ChecksumMismatch => {
+                write!(f, "Failed to decode base64: Check character does not match.")
+            }
+            DecodeError::MissingChecksum => {
+                write!(f, "Failed to decode base64: Checksum line not found.")
+            }
+            DecodeError::AuthFailed => {
+                write!(f, "Failed to decode base64: Authentication tag does not match.")
+            }
+            DecodeError::NotUtf8 { valid_up_to } => write!(
+                f,
+                "Failed to decode base64: Decoded data is not UTF-8 after byte {}.",
+                valid_up_to
+            ),
+        }?;
+
+        #[cfg(feature = "error-context")]
+        if let DecodeError::InvalidByte { context, .. }
+        | DecodeError::NonAsciiByte { context, .. }
+        | DecodeError::NonAsciiChar { context, .. }
+        | DecodeError::WrongAlphabet { context, .. } = self
+        {
+            if context.len > 0 {
+                write!(f, " Near: {}", context.as_str())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for DecodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_decode;
+
+    #[test]
+    fn context_points_at_the_bad_character() {
+        let input = "TG9uZyBsaXZlIGVh$3RlciBlZ2dzIDop";
+        let context = base64_decode(input).unwrap_err().context(input);
+        assert!(context.starts_with("Failed to decode base64"));
+        assert!(context.ends_with(": ...aXZlIGVh[$]3RlciBlZ..."));
+
+        let short = "TW$u";
+        let context = base64_decode(short).unwrap_err().context(short);
+        assert!(context.ends_with(": TW[$]u"));
+    }
+
+    #[test]
+    fn context_handles_multi_byte_and_unindexed_errors() {
+        let input = "TWFu\u{2026}";
+        let context = base64_decode(input).unwrap_err().context(input);
+        assert!(context.ends_with(": TWFu[\u{2026}]"));
+
+        assert_eq!(
+            DecodeError::InvalidPadding.context("TWF"),
+            DecodeError::InvalidPadding.to_string()
+        );
+    }
+
+    #[test]
+    fn non_ascii_characters_are_reported_whole() {
+        let err = base64_decode("TWFu\u{2026}").unwrap_err();
+        assert_eq!(err, DecodeError::non_ascii_char(4, '\u{2026}'));
+        assert_eq!(err.index(), Some(4));
+        assert!(err.to_string().contains("'\u{2026}' (U+2026) at index 4."));
+
+        // Raw bytes have no characters to report.
+        assert_eq!(
+            crate::base64_decode_bytes("TWFu\u{2026}".as_bytes()),
+            Err(DecodeError::invalid_byte(4, 0xe2))
+        );
+    }
+
+    #[test]
+    fn every_string_decoder_reports_non_ascii_characters_whole() {
+        use crate::*;
+
+        let input = "TWFu\u{2026}A";
+        let expected = Err(DecodeError::non_ascii_char(4, '\u{2026}'));
+        let mut buffer = Vec::with_capacity(6);
+        let mut validator = Validator::new();
+        validator.update("TWFu");
+        validator.update("\u{2026}A");
+        let results: [(&str, Result<(), DecodeError>); 17] = [
+            ("base64_decode", base64_decode(input).map(drop)),
+            (
+                "decode_strict_aligned",
+                decode_strict_aligned(input).map(drop),
+            ),
+            (
+                "decode_uninit",
+                decode_uninit(input, buffer.spare_capacity_mut()).map(drop),
+            ),
+            ("decode_simd", decode_simd(input).map(drop)),
+            (
+                "decode_with_progress",
+                decode_with_progress(input, |_, _| {}).map(drop),
+            ),
+            (
+                "decode_partial_on_error",
+                decode_partial_on_error(input).map(drop).map_err(|e| e.0),
+            ),
+            (
+                "decode_hashing",
+                decode_hashing(input, &mut std::collections::hash_map::DefaultHasher::new())
+                    .map(drop),
+            ),
+            (
+                "decode_matches_prefix",
+                decode_matches_prefix(input, b"Many").map(drop),
+            ),
+            ("decode_byte_at", decode_byte_at(input, 3).map(drop)),
+            ("decode_detect", decode_detect(input).map(drop)),
+            ("base64_decode_nopad", base64_decode_nopad(input).map(drop)),
+            ("decode_url_query", decode_url_query(input).map(drop)),
+            (
+                "decode_chunks",
+                decode_chunks(["TWFu", "\u{2026}A"]).map(drop),
+            ),
+            ("Validator", validator.finish()),
+            (
+                "transcode",
+                transcode(input, &Alphabet::STANDARD, &Alphabet::URL_SAFE).map(drop),
+            ),
+            (
+                "semantically_equal",
+                semantically_equal(input, "TWFu").map(drop),
+            ),
+            ("decode_with_check", decode_with_check(input).map(drop)),
+        ];
+        for (name, result) in results {
+            assert_eq!(result, expected, "{}", name);
+        }
+        assert_eq!(
+            decode_ipv6("AAAAAAAAAAAAAAAAAAAA\u{e9}"),
+            Err(DecodeError::non_ascii_char(20, '\u{e9}'))
+        );
+    }
+
+    #[cfg(feature = "error-context")]
+    #[test]
+    fn errors_carry_their_context() {
+        let input = "TG9uZyBsaXZlIGVh$3RlciBlZ2dzIDop";
+        match base64_decode(input).unwrap_err() {
+            DecodeError::InvalidByte { context, .. } => {
+                assert_eq!(context.as_str(), "...aXZlIGVh[$]3RlciBlZ...");
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        // Bytes after a non-UTF-8 one keep their place.
+        let err = DecodeError::invalid_byte(3, b'$').report(b"\xffTW$u");
+        assert!(err.to_string().ends_with("Near: \\xffTW[$]u"));
+        // Escaped sides are cut short to fit.
+        let err = DecodeError::non_ascii_byte(8, 0xff).report(&[0xff; 17]);
+        let side = "\\xff".repeat(7);
+        assert!(err
+            .to_string()
+            .ends_with(&format!("Near: ...{}[\\xff]{}...", side, side)));
+        let err = crate::Base64Config::new()
+            .decode(b"TW\xfe\xffu")
+            .unwrap_err();
+        assert!(err.to_string().ends_with("Near: TW[\\xfe]\\xffu"));
+
+        let input = "QUJDREVGR0hJSktM\u{1f600}QUJD";
+        match base64_decode(input).unwrap_err() {
+            DecodeError::NonAsciiChar { context, .. } => {
+                assert_eq!(context.as_str(), "...R0hJSktM[\\xf0\\x9f\\x98\\x80]QUJD");
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Decoders specialized for well-formed input
+//!
+//! These skip the bookkeeping the general decoder needs for arbitrary
+//! input and instead work on whole 4 character groups.
+
+use std::mem::MaybeUninit;
+
+use crate::{DecodeError, DECODE_TABLE, INVALID, PADDING};
+
+/// Decodes clean, padded Base64 whose length is a multiple of 4.
+///
+/// Every group of 4 characters is turned into 3 bytes with a table
+/// lookup. Padding is only looked for in the final group, and no
+/// whitespace is accepted anywhere. For input that meets these rules the
+/// result is identical to `base64_decode`, just faster.
+///
+/// Parameters:
+/// - `data`: A padded Base64 encoded string.
+///
+/// Returns: The decoded bytes, or:
+/// - `InvalidLength` if `data.len()` is not a multiple of 4.
+/// - `InvalidByte` for a byte outside the charset, including padding
+///   anywhere other than the end of the last group. Misplaced padding is
+///   found before the full pass and reported even if a foreign byte comes
+///   earlier.
+/// - `NonAsciiChar` for a character outside ASCII.
+/// - `InvalidPadding` if the last group holds more than 2 padding bytes.
+pub fn decode_strict_aligned(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_groups(data.as_bytes()).map_err(|err| err.report_str(data))
+}
+
+/// Like `decode_strict_aligned`, but writes into `out` without it having
+/// to be initialized first.
+///
+/// For large buffers, zero filling a buffer only to overwrite it is a
+/// measurable cost; this lets the decoder write into, say, the spare
+/// capacity of a `Vec` instead.
+///
+/// Example:
+/// ```
+/// use base64::decode_uninit;
+///
+/// let mut buffer = Vec::with_capacity(6);
+/// let decoded = decode_uninit("TWFueQ==", buffer.spare_capacity_mut()).unwrap();
+/// assert_eq!(decoded, b"Many");
+/// ```
+///
+/// Parameters:
+/// - `data`: A padded Base64 encoded string.
+/// - `out`: Receives the decoded bytes.
+///
+/// Returns: The initialized start of `out` holding the decoded bytes, or
+/// the `DecodeError` documented on `decode_strict_aligned`. On error, the
+/// contents of `out` are unspecified.
+///
+/// Panics if `out` is shorter than `data.len() / 4 * 3`, before anything
+/// is decoded.
+pub fn decode_uninit<'a>(
+    data: &str,
+    out: &'a mut [MaybeUninit<u8>],
+) -> Result<&'a mut [u8], DecodeError> {
+    let input = data.as_bytes();
+    let needed = input.len() / 4 * 3;
+    assert!(
+        out.len() >= needed,
+        "output holds {} bytes but {} are needed",
+        out.len(),
+        needed
+    );
+
+    let mut written = 0;
+    for_each_group(input, |bytes| {
+        for (slot, &byte) in out[written..].iter_mut().zip(bytes) {
+            slot.write(byte);
+        }
+        written += bytes.len();
+    })
+    .map_err(|err| err.report_str(data))?;
+    // SAFETY: the first `written` bytes of `out` were just written, and
+    // `MaybeUninit<u8>` has the layout of `u8`.
+    Ok(unsafe { std::slice::from_raw_parts_mut(out.as_mut_ptr().cast::<u8>(), written) })
+}
+
+/// Decodes `data` group by group, as described on `decode_strict_aligned`.
+pub(crate) fn decode_groups(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut output = Vec::with_capacity(data.len() / 4 * 3);
+    for_each_group(data, |bytes| output.extend_from_slice(bytes))?;
+    Ok(output)
+}
+
+/// Checks `data` and hands the 1 to 3 decoded bytes of each group to
+/// `emit`, in order.
+pub(crate) fn for_each_group(data: &[u8], mut emit: impl FnMut(&[u8])) -> Result<(), DecodeError> {
+    if !data.len().is_multiple_of(4) {
+        return Err(DecodeError::InvalidLength { len: data.len() });
+    }
+    check_structure(data)?;
+
+    let last_group_start = data.len().saturating_sub(4);
+
+    for (group_index, group) in data.chunks_exact(4).enumerate() {
+        let start = group_index * 4;
+        let padding = if start == last_group_start {
+            group
+                .iter()
+                .rev()
+                .take_while(|&&b| b == PADDING as u8)
+                .count()
+        } else {
+            0
+        };
+        if padding > 2 {
+            return Err(DecodeError::InvalidPadding);
+        }
+
+        let mut sextets = [0u8; 4];
+        for (i, &byte) in group[..4 - padding].iter().enumerate() {
+            let value = DECODE_TABLE[byte as usize];
+            if value == INVALID {
+                return Err(DecodeError::invalid_byte(start + i, byte));
+            }
+            sextets[i] = value;
+        }
+
+        let bits = (sextets[0] as u32) << 18
+            | (sextets[1] as u32) << 12
+            | (sextets[2] as u32) << 6
+            | sextets[3] as u32;
+        emit(&bits.to_be_bytes()[1..4 - padding]);
+    }
+
+    Ok(())
+}
+
+/// Rejects misplaced padding before any decoding is done.
+///
+/// Well-formed input never starts with padding and only has it in its
+/// last 4 characters, so both are cheap to check up front: the first is a
+/// single comparison, the second a plain byte search.
+pub(crate) fn check_structure(data: &[u8]) -> Result<(), DecodeError> {
+    if data.first() == Some(&(PADDING as u8)) {
+        return Err(DecodeError::invalid_byte(0, PADDING as u8));
+    }
+    let body = &data[..data.len().saturating_sub(4)];
+    match body.iter().position(|&b| b == PADDING as u8) {
+        Some(index) => Err(DecodeError::invalid_byte(index, PADDING as u8)),
+        None => Ok(()),
+    }
+}
+
+/// Decodes whole 4 character groups into `out` without checking them.
+///
+/// This is the bare inner loop of `decode_strict_aligned`: one table
+/// lookup per character and no validation at all. It is meant for hot
+/// paths whose input has already been checked, e.g. with
+/// `is_valid_base64`.
+///
+/// **The caller must ensure that:**
+/// - `data.len()` is a multiple of 4; a trailing partial group is ignored.
+/// - `data` holds no padding and nothing outside the standard alphabet.
+///   Such characters are not detected and silently produce wrong bytes.
+///
+/// Breaking these rules gives garbage output but never undefined
+/// behavior.
+///
+/// Parameters:
+/// - `data`: Unpadded standard Base64 whose length is a multiple of 4.
+/// - `out`: Receives the `data.len() / 4 * 3` decoded bytes.
+///
+/// Panics if `out` is shorter than `data.len() / 4 * 3`.
+pub fn decode_groups_unchecked(data: &[u8], out: &mut [u8]) {
+    let needed = data.len() / 4 * 3;
+    assert!(
+        out.len() >= needed,
+        "output holds {} bytes but {} are needed",
+        out.len(),
+        needed
+    );
+
+    for (group, out) in data.chunks_exact(4).zip(out.chunks_exact_mut(3)) {
+        let bits = (DECODE_TABLE[group[0] as usize] as u32 & 0x3f) << 18
+            | (DECODE_TABLE[group[1] as usize] as u32 & 0x3f) << 12
+            | (DECODE_TABLE[group[2] as usize] as u32 & 0x3f) << 6
+            | DECODE_TABLE[group[3] as usize] as u32 & 0x3f;
+        out.copy_from_slice(&bits.to_be_bytes()[1..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_encode};
+
+    #[test]
+    fn aligned_matches_general_decoder() {
+        for len in 0..64usize {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + len) as u8).collect();
+            let encoded = base64_encode(&data);
+            assert_eq!(decode_strict_aligned(&encoded), base64_decode(&encoded));
+            assert_eq!(decode_strict_aligned(&encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn aligned_rejects_malformed_input() {
+        assert_eq!(
+            decode_strict_aligned("QUJDRA="),
+            Err(DecodeError::InvalidLength { len: 7 })
+        );
+        assert_eq!(
+            decode_strict_aligned("QQ==QUJD"),
+            Err(DecodeError::invalid_byte(2, b'='))
+        );
+        assert_eq!(
+            decode_strict_aligned("QUJD Q=="),
+            Err(DecodeError::invalid_byte(4, b' '))
+        );
+        assert_eq!(
+            decode_strict_aligned("Q==="),
+            Err(DecodeError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn structure_check_rejects_only_misplaced_padding() {
+        for len in 0..64usize {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + len) as u8).collect();
+            assert_eq!(check_structure(base64_encode(&data).as_bytes()), Ok(()));
+        }
+        assert_eq!(
+            check_structure(b"=QUJ"),
+            Err(DecodeError::invalid_byte(0, b'='))
+        );
+        assert_eq!(
+            decode_strict_aligned("===="),
+            Err(DecodeError::invalid_byte(0, b'='))
+        );
+        assert_eq!(
+            decode_strict_aligned("$UJD=UJDQUJD"),
+            Err(DecodeError::invalid_byte(4, b'='))
+        );
+    }
+
+    #[test]
+    fn uninit_output_matches_aligned_decoder() {
+        for len in 0..64usize {
+            let data: Vec<u8> = (0..len).map(|i| (i * 53 + len) as u8).collect();
+            let encoded = base64_encode(&data);
+            let mut buffer = Vec::with_capacity(encoded.len() / 4 * 3);
+            let decoded = decode_uninit(&encoded, buffer.spare_capacity_mut()).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        let mut out = [MaybeUninit::uninit(); 6];
+        assert_eq!(
+            decode_uninit("QUJD$Q==", &mut out),
+            Err(DecodeError::invalid_byte(4, b'$'))
+        );
+        assert_eq!(
+            decode_uninit("QUJ", &mut out),
+            Err(DecodeError::InvalidLength { len: 3 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "needed")]
+    fn uninit_output_needs_room() {
+        decode_uninit("QUJDREVG", &mut [MaybeUninit::uninit(); 5]).ok();
+    }
+
+    #[test]
+    fn unchecked_groups_match_checked_decoder() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 151 % 256) as u8).collect();
+        let encoded = base64_encode(&data);
+        let mut out = vec![0u8; data.len()];
+        decode_groups_unchecked(encoded.as_bytes(), &mut out);
+        assert_eq!(out, data);
+
+        // Garbage in, garbage out, but within bounds.
+        let mut out = [0u8; 3];
+        decode_groups_unchecked(b"!!==", &mut out);
+    }
+
+    #[test]
+    #[should_panic(expected = "needed")]
+    fn unchecked_groups_need_room() {
+        decode_groups_unchecked(b"QUJDREVG", &mut [0u8; 5]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Short identifiers derived from data
+
+use crate::Base64Config;
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Hashes `data` with 128-bit FNV-1a.
+fn fnv1a_128(data: &[u8]) -> u128 {
+    data.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u128).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Derives a short, stable, URL-safe identifier from `data`, e.g. for
+/// display IDs.
+///
+/// The data is hashed with 128-bit FNV-1a, and the hash is encoded with
+/// the URL-safe alphabet without padding, giving at most 22 characters.
+/// FNV is fast and stable across platforms and releases, but it is not a
+/// cryptographic hash: IDs can be forged on purpose, so never use them to
+/// authenticate data.
+///
+/// Example:
+/// ```
+/// use base64::short_id;
+///
+/// let id = short_id(b"report-2024.pdf", 8);
+/// assert_eq!(id.len(), 8);
+/// assert_eq!(id, short_id(b"report-2024.pdf", 8));
+/// ```
+///
+/// Parameters:
+/// - `data`: The data to identify.
+/// - `chars`: The length of the ID; anything over 22 gives 22.
+///
+/// Returns: The first `chars` characters of the encoded hash.
+pub fn short_id(data: &[u8], chars: usize) -> String {
+    let mut id = Base64Config::URL_SAFE_NO_PAD.encode(&fnv1a_128(data).to_be_bytes());
+    id.truncate(chars);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn ids_are_stable_and_usually_differ() {
+        assert_eq!(fnv1a_128(b""), FNV_OFFSET_BASIS);
+        assert_eq!(short_id(b"hello", 10), short_id(b"hello", 10));
+        assert!(short_id(b"hello", 22).starts_with(&short_id(b"hello", 10)));
+        assert_eq!(short_id(b"hello", 100).len(), 22);
+        assert_eq!(short_id(b"hello", 0), "");
+
+        let ids: HashSet<String> = (0..1000u32)
+            .map(|i| short_id(&i.to_le_bytes(), 8))
+            .collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(ids.iter().all(|id| id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')));
+    }
+}
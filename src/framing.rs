@@ -0,0 +1,348 @@
+//! Helpers for Base64 wrapped in simple framing formats
+
+use std::ops::Range;
+
+use crate::{base64_decode, base64_encode, Alphabet, Base64Encoder, DecodeError, PADDING};
+
+/// Encodes `data` and puts `prefix` in front, e.g. for versioned tokens.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `prefix`: The scheme prefix such as `"v1:"`.
+///
+/// Returns: `prefix` followed by the Base64 encoded string.
+pub fn encode_with_prefix(data: &[u8], prefix: &str) -> String {
+    let mut encoded = String::from(prefix);
+    encoded.push_str(&base64_encode(data));
+    encoded
+}
+
+/// Checks that `data` starts with `prefix`, strips it and decodes the rest.
+///
+/// Parameters:
+/// - `data`: The prefixed Base64 encoded string.
+/// - `prefix`: The scheme prefix that must be present.
+///
+/// Returns: The decoded bytes, `DecodeError::MissingPrefix` if `data` does
+/// not start with `prefix`, or the error from decoding the remainder. Its
+/// indices are relative to the start of the remainder.
+pub fn decode_with_prefix(data: &str, prefix: &str) -> Result<Vec<u8>, DecodeError> {
+    let encoded = data
+        .strip_prefix(prefix)
+        .ok_or(DecodeError::MissingPrefix)?;
+    base64_decode(encoded)
+}
+
+/// Decodes the Base64 token at the start of `data` and returns the rest
+/// of the input, for parsers that carry on after the token.
+///
+/// The token is the leading run of standard alphabet characters followed
+/// by its run of padding; whatever comes after is left alone.
+///
+/// Example:
+/// ```
+/// use base64::decode_and_split;
+///
+/// let (token, rest) = decode_and_split("TWFueQ==;other=data").unwrap();
+/// assert_eq!(token, b"Many");
+/// assert_eq!(rest, ";other=data");
+/// ```
+///
+/// Returns: The decoded token and the remainder of `data`, or the error
+/// from decoding the token.
+pub fn decode_and_split(data: &str) -> Result<(Vec<u8>, &str), DecodeError> {
+    let bytes = data.as_bytes();
+    let chars = bytes
+        .iter()
+        .take_while(|&&b| Alphabet::STANDARD.contains(b))
+        .count();
+    let padding = bytes[chars..]
+        .iter()
+        .take_while(|&&b| b == PADDING as u8)
+        .count();
+    let (token, rest) = data.split_at(chars + padding);
+    Ok((base64_decode(token)?, rest))
+}
+
+/// Decodes `data` after stripping one pair of matching surrounding `"` or
+/// `'` quotes, as often copied along from logs, JSON or XML.
+///
+/// Unquoted input is decoded as is. A quote at only one end, or two
+/// different quotes, is an error.
+///
+/// Parameters:
+/// - `data`: The Base64 encoded string, possibly quoted.
+///
+/// Returns: The decoded bytes, or a `DecodeError` whose index counts from
+/// the start of `data`, quotes included. A mismatched quote is reported as
+/// the `InvalidByte` it is.
+pub fn decode_quoted(data: &str) -> Result<Vec<u8>, DecodeError> {
+    let bytes = data.as_bytes();
+    match bytes {
+        [open @ (b'"' | b'\''), .., close] if open == close => {
+            base64_decode(&data[1..data.len() - 1]).map_err(|err| err.shifted(1))
+        }
+        [quote @ (b'"' | b'\''), ..] => Err(DecodeError::invalid_byte(0, *quote).report_str(data)),
+        _ => base64_decode(data),
+    }
+}
+
+/// The magic bytes that open every container from `encode_container`.
+pub const CONTAINER_MAGIC: [u8; 2] = [0xb6, 0x40];
+
+/// Encodes only the `keep` bytes of every `stride` byte window of `data`,
+/// e.g. the payload of fixed-layout records with interleaved metadata.
+///
+/// The kept bytes are fed straight to the encoder, so the result is the
+/// same as filtering them into a new buffer and encoding that, without
+/// the allocation. A final partial window contributes whatever part of
+/// `keep` it covers.
+///
+/// Panics if `stride` is 0 or `keep` does not lie within `0..stride`.
+///
+/// Example:
+/// ```
+/// use base64::encode_strided;
+///
+/// // One header byte, then a 3 byte payload, per record.
+/// assert_eq!(encode_strided(b"\x01Man\x02has", 4, 1..4), "TWFuaGFz");
+/// ```
+///
+/// Parameters:
+/// - `data`: The records, back to back.
+/// - `stride`: The size of one record.
+/// - `keep`: The byte range to encode within each record.
+///
+/// Returns: The Base64 encoded kept bytes.
+pub fn encode_strided(data: &[u8], stride: usize, keep: Range<usize>) -> String {
+    assert!(
+        stride > 0 && keep.start <= keep.end && keep.end <= stride,
+        "kept range {:?} must lie within a stride of {}",
+        keep,
+        stride
+    );
+    let windows = data.len().div_ceil(stride);
+    let mut encoded = String::with_capacity((windows * keep.len()).div_ceil(3) * 4);
+    let mut encoder = Base64Encoder::new();
+    for window in data.chunks(stride) {
+        let end = keep.end.min(window.len());
+        let start = keep.start.min(end);
+        encoder.update_into(&window[start..end], &mut encoded);
+    }
+    encoder.finalize_into(&mut encoded);
+    encoded
+}
+
+/// Encodes `data` in a small self-describing container.
+///
+/// The encoded bytes are `CONTAINER_MAGIC`, then `version`, then `data`,
+/// so readers can recognize the format and handle older or newer
+/// payloads.
+///
+/// Parameters:
+/// - `version`: The payload format version.
+/// - `data`: The payload.
+///
+/// Returns: The Base64 encoded container.
+pub fn encode_container(version: u8, data: &[u8]) -> String {
+    let mut framed = Vec::with_capacity(data.len() + 3);
+    framed.extend_from_slice(&CONTAINER_MAGIC);
+    framed.push(version);
+    framed.extend_from_slice(data);
+    base64_encode(&framed)
+}
+
+/// Decodes a container made by `encode_container`.
+///
+/// Returns: The version and payload, `DecodeError::BadMagic` if the
+/// decoded bytes do not start with `CONTAINER_MAGIC` and a version, or
+/// the error from decoding `data`.
+pub fn decode_container(data: &str) -> Result<(u8, Vec<u8>), DecodeError> {
+    let mut decoded = base64_decode(data)?;
+    match decoded[..] {
+        [m0, m1, version, ..] if [m0, m1] == CONTAINER_MAGIC => {
+            decoded.drain(..3);
+            Ok((version, decoded))
+        }
+        _ => Err(DecodeError::BadMagic),
+    }
+}
+
+/// Decodes the Base64 value of a TLV (type-length-value) record and checks
+/// it against the record's declared length.
+///
+/// In such formats the length field states how many bytes the value
+/// holds once decoded, so a mismatch means the record is corrupt or was
+/// truncated.
+///
+/// Example:
+/// ```
+/// use base64::{decode_tlv_value, DecodeError};
+///
+/// // A record of type 7 whose 5 byte value is stored as Base64.
+/// let (record_type, declared_len, value) = (7u8, 5usize, "aGVsbG8=");
+/// assert_eq!(record_type, 7);
+/// assert_eq!(decode_tlv_value(value, declared_len).unwrap(), b"hello");
+/// assert_eq!(
+///     decode_tlv_value(value, 6),
+///     Err(DecodeError::LengthMismatch { expected: 6, actual: 5 })
+/// );
+/// ```
+///
+/// Parameters:
+/// - `b64`: The Base64 encoded value.
+/// - `declared_len`: The decoded length stated by the record.
+///
+/// Returns: The value, `DecodeError::LengthMismatch` if it does not have
+/// exactly `declared_len` bytes, or the error from decoding `b64`.
+pub fn decode_tlv_value(b64: &str, declared_len: usize) -> Result<Vec<u8>, DecodeError> {
+    let value = base64_decode(b64)?;
+    if value.len() != declared_len {
+        return Err(DecodeError::LengthMismatch {
+            expected: declared_len,
+            actual: value.len(),
+        });
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_round_trip() {
+        let token = encode_with_prefix(b"secret", "enc$");
+        assert_eq!(token, "enc$c2VjcmV0");
+        assert_eq!(decode_with_prefix(&token, "enc$").unwrap(), b"secret");
+        assert_eq!(decode_with_prefix("c2VjcmV0", "").unwrap(), b"secret");
+    }
+
+    #[test]
+    fn prefix_errors() {
+        assert_eq!(
+            decode_with_prefix("v2:c2VjcmV0", "v1:"),
+            Err(DecodeError::MissingPrefix)
+        );
+        assert_eq!(
+            decode_with_prefix("v1:c2Vj$mV0", "v1:"),
+            Err(DecodeError::invalid_byte(4, b'$'))
+        );
+    }
+
+    #[test]
+    fn container_round_trip() {
+        let encoded = encode_container(2, b"payload");
+        assert_eq!(
+            decode_container(&encoded).unwrap(),
+            (2, b"payload".to_vec())
+        );
+        assert_eq!(
+            decode_container(&encode_container(0, b"")).unwrap(),
+            (0, Vec::new())
+        );
+    }
+
+    #[test]
+    fn container_with_wrong_magic_fails() {
+        assert_eq!(
+            decode_container(&base64_encode(b"\xb6\x41\x01data")),
+            Err(DecodeError::BadMagic)
+        );
+        assert_eq!(
+            decode_container(&base64_encode(b"\xb6\x40")),
+            Err(DecodeError::BadMagic)
+        );
+        assert!(matches!(
+            decode_container("not base64"),
+            Err(DecodeError::InvalidByte { .. })
+        ));
+    }
+
+    #[test]
+    fn tlv_value_length_is_checked() {
+        assert_eq!(decode_tlv_value("AAEC", 3).unwrap(), [0, 1, 2]);
+        assert_eq!(decode_tlv_value("", 0).unwrap(), b"");
+        assert_eq!(
+            decode_tlv_value("AAEC", 4),
+            Err(DecodeError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(decode_tlv_value("AAE", 2), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    fn strided_matches_filter_then_encode() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for stride in 1..9 {
+            for start in 0..=stride {
+                for end in start..=stride {
+                    let filtered: Vec<u8> = data
+                        .chunks(stride)
+                        .flat_map(|w| &w[start.min(w.len())..end.min(w.len())])
+                        .copied()
+                        .collect();
+                    assert_eq!(
+                        encode_strided(&data, stride, start..end),
+                        base64_encode(&filtered)
+                    );
+                }
+            }
+        }
+        assert_eq!(encode_strided(b"", 4, 1..3), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "within a stride")]
+    fn strided_range_must_fit_the_stride() {
+        encode_strided(b"abcdef", 3, 1..4);
+    }
+
+    #[test]
+    fn token_is_split_from_the_rest() {
+        assert_eq!(
+            decode_and_split("TWFuQQ==;other=data").unwrap(),
+            (b"ManA".to_vec(), ";other=data")
+        );
+        assert_eq!(
+            decode_and_split("TWFu;other=data").unwrap(),
+            (b"Man".to_vec(), ";other=data")
+        );
+        assert_eq!(decode_and_split("TWFu").unwrap(), (b"Man".to_vec(), ""));
+        assert_eq!(decode_and_split(";x").unwrap(), (Vec::new(), ";x"));
+        assert_eq!(
+            decode_and_split("TWF;other"),
+            Err(DecodeError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn surrounding_quotes_are_stripped() {
+        for input in ["\"TG9uZw==\"", "'TG9uZw=='", "TG9uZw=="] {
+            assert_eq!(decode_quoted(input).unwrap(), b"Long");
+        }
+        assert_eq!(decode_quoted("\"\"").unwrap(), b"");
+    }
+
+    #[test]
+    fn mismatched_quotes_are_errors() {
+        assert_eq!(
+            decode_quoted("\"TG9uZw=='"),
+            Err(DecodeError::invalid_byte(0, b'"'))
+        );
+        assert_eq!(
+            decode_quoted("'TG9uZw=="),
+            Err(DecodeError::invalid_byte(0, b'\''))
+        );
+        assert_eq!(
+            decode_quoted("TG9uZw==\""),
+            Err(DecodeError::invalid_byte(8, b'"'))
+        );
+        assert_eq!(
+            decode_quoted("\"TG$uZw==\""),
+            Err(DecodeError::invalid_byte(3, b'$'))
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Relating decoded bytes back to the Base64 characters they came from
+
+use std::ops::Range;
+
+use crate::decoder::DecodeState;
+use crate::{base64_decode, Base64Config, DecodeError, Padding, DECODE_TABLE, INVALID, PADDING};
+
+/// Returns the range of sextet positions that hold the bits of decoded
+/// byte `index`.
+///
+/// Byte `i` is made of bits `8i..8i + 8` of the sextet stream, and
+/// sextet `j` holds bits `6j..6j + 6`, so every byte spans 2 characters.
+pub(crate) fn sextet_span(index: usize) -> Range<usize> {
+    let first_bit = index * 8;
+    first_bit / 6..(first_bit + 7) / 6 + 1
+}
+
+/// Decodes `data` and pairs every output byte with the range of input
+/// character indices that contributed bits to it.
+///
+/// Because 4 characters regroup into 3 bytes, every byte is spread over
+/// 2 neighboring characters, and each character is shared by up to 2
+/// bytes. Useful for tools that highlight where a byte came from.
+///
+/// Example:
+/// ```
+/// use base64::decode_with_spans;
+///
+/// let spans = decode_with_spans("TWFu").unwrap();
+/// assert_eq!(spans, [(b'M', 0..2), (b'a', 1..3), (b'n', 2..4)]);
+/// ```
+///
+/// Returns: The decoded bytes with their spans, or a `DecodeError`.
+pub fn decode_with_spans(data: &str) -> Result<Vec<(u8, Range<usize>)>, DecodeError> {
+    Ok(base64_decode(data)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, byte)| (byte, sextet_span(i)))
+        .collect())
+}
+
+/// Decodes only byte `index` of the data `data` encodes.
+///
+/// The 2 characters holding its bits are found with the same arithmetic
+/// as `decode_with_spans`, so reading one byte of a large blob costs the
+/// same as reading it from a short one. Only those 2 characters are
+/// checked; the rest of `data` is assumed to be valid standard Base64,
+/// padded or not.
+///
+/// Example:
+/// ```
+/// use base64::decode_byte_at;
+///
+/// assert_eq!(decode_byte_at("TWFueQ==", 3), Ok(b'y'));
+/// ```
+///
+/// Parameters:
+/// - `data`: A Base64 encoded string.
+/// - `index`: The position of the wanted byte in the decoded data.
+///
+/// Returns: The byte, `InvalidByte` or `NonAsciiChar` if one of its
+/// characters is not in the charset, or `IndexOutOfRange` if the data is
+/// shorter.
+pub fn decode_byte_at(data: &str, index: usize) -> Result<u8, DecodeError> {
+    let input = data.as_bytes();
+    let padding = input
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|&&b| b == PADDING as u8)
+        .count();
+    let len = (input.len() - padding) * 6 / 8;
+    if index >= len {
+        return Err(DecodeError::IndexOutOfRange { index, len });
+    }
+
+    // The 12 bits of the 2 sextets hold the byte at an offset of 0, 2 or
+    // 4 bits from the start.
+    let span = sextet_span(index);
+    let mut bits = 0u16;
+    for position in span.clone() {
+        let value = DECODE_TABLE[input[position] as usize];
+        if value == INVALID {
+            return Err(DecodeError::invalid_byte(position, input[position]).report_str(data));
+        }
+        bits = bits << 6 | value as u16;
+    }
+    let offset = index * 8 - span.start * 6;
+    Ok((bits >> (4 - offset)) as u8)
+}
+
+/// Checks whether the data `data` encodes starts with `magic`, decoding
+/// no more of it than needed.
+///
+/// Decoding stops as soon as `magic.len()` bytes are out, so telling the
+/// type of a large payload by its magic number costs the same as for a
+/// short one. Only the characters read are checked; whatever follows them
+/// is not looked at.
+///
+/// Example:
+/// ```
+/// use base64::decode_matches_prefix;
+///
+/// assert_eq!(decode_matches_prefix("JVBERi0xLjc=", b"%PDF"), Ok(true));
+/// ```
+///
+/// Parameters:
+/// - `data`: Standard Base64, padded or not.
+/// - `magic`: The bytes the decoded data should start with.
+///
+/// Returns: Whether the decoded data starts with `magic`, which it cannot
+/// if it is shorter, or the first `DecodeError` in the characters read.
+pub fn decode_matches_prefix(data: &str, magic: &[u8]) -> Result<bool, DecodeError> {
+    let config = Base64Config::STANDARD.padding(Padding::Optional);
+    let mut state = DecodeState::new();
+    let mut decoded = Vec::with_capacity(magic.len() + 3);
+    let mut bytes = data.bytes().enumerate();
+    while decoded.len() < magic.len() {
+        match bytes.next() {
+            Some((index, byte)) => state
+                .push(&config, index, byte, &mut decoded)
+                .map_err(|err| err.report_str(data))?,
+            None => {
+                state
+                    .finish(&config, &mut decoded)
+                    .map_err(|err| err.report_str(data))?;
+                break;
+            }
+        }
+    }
+    Ok(decoded.starts_with(magic))
+}
+
+/// Returns the bounds of the bytes whose encoding starts with `b64_prefix`,
+/// e.g. to turn a prefix search over encoded keys into a range scan over
+/// the raw keys.
+///
+/// Each character fixes 6 bits, so a prefix of `k` characters fixes the
+/// first `6k / 8` bytes and, unless `6k` is a multiple of 8, the top 2, 4
+/// or 6 bits of the byte after them. The bounds are those bytes, with that
+/// last partial byte filled up with zeros in the lower bound and with ones
+/// in the upper one. The encoding of `data` starts with `b64_prefix`
+/// exactly when the first `lower.len()` bytes of `data` lie between the
+/// two, inclusive.
+///
+/// Example:
+/// ```
+/// use base64::prefix_byte_bounds;
+///
+/// // `T` and `W` fix the byte `M` and 4 bits of the next one.
+/// assert_eq!(prefix_byte_bounds("TW"), Ok((b"M\x60".to_vec(), b"M\x6f".to_vec())));
+/// ```
+///
+/// Parameters:
+/// - `b64_prefix`: The start of some standard Base64, without padding.
+///
+/// Returns: The inclusive lower and upper bounds, which always have the
+/// same length, or `InvalidByte` for a character outside the charset
+/// (`NonAsciiChar` if it is not ASCII).
+pub fn prefix_byte_bounds(b64_prefix: &str) -> Result<(Vec<u8>, Vec<u8>), DecodeError> {
+    let mut lower = Vec::with_capacity(b64_prefix.len() * 3 / 4 + 1);
+    let mut bits = 0u16;
+    let mut pending = 0;
+    for (index, byte) in b64_prefix.bytes().enumerate() {
+        let value = DECODE_TABLE[byte as usize];
+        if value == INVALID {
+            return Err(DecodeError::invalid_byte(index, byte).report_str(b64_prefix));
+        }
+        bits = bits << 6 | value as u16;
+        pending += 6;
+        if pending >= 8 {
+            pending -= 8;
+            lower.push((bits >> pending) as u8);
+            bits &= (1 << pending) - 1;
+        }
+    }
+
+    let mut upper = lower.clone();
+    if pending > 0 {
+        let free = 8 - pending;
+        lower.push((bits << free) as u8);
+        upper.push((bits << free | ((1 << free) - 1)) as u8);
+    }
+    Ok((lower, upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_cover_the_contributing_characters() {
+        let spans = decode_with_spans("TWFueQ==").unwrap();
+        assert_eq!(
+            spans,
+            [(b'M', 0..2), (b'a', 1..3), (b'n', 2..4), (b'y', 4..6)]
+        );
+        assert!(decode_with_spans("").unwrap().is_empty());
+        assert!(decode_with_spans("TW$u").is_err());
+    }
+
+    #[test]
+    fn single_bytes_match_full_decode() {
+        let data: Vec<u8> = (0..100u32).map(|i| (i * 89 % 256) as u8).collect();
+        for len in [1, 2, 3, 4, 98, 99, 100] {
+            let encoded = crate::base64_encode(&data[..len]);
+            for (index, &byte) in data[..len].iter().enumerate() {
+                assert_eq!(decode_byte_at(&encoded, index), Ok(byte));
+                assert_eq!(
+                    decode_byte_at(encoded.trim_end_matches('='), index),
+                    Ok(byte)
+                );
+            }
+            assert_eq!(
+                decode_byte_at(&encoded, len),
+                Err(DecodeError::IndexOutOfRange { index: len, len })
+            );
+        }
+        assert_eq!(
+            decode_byte_at("TWFu$Q==", 3),
+            Err(DecodeError::invalid_byte(4, b'$'))
+        );
+    }
+
+    #[test]
+    fn bytes_within_the_bounds_encode_to_the_prefix() {
+        let encoded = crate::base64_encode(b"\x00\x7f\x80\xff\x42\x13\x37");
+        for len in 0..=9 {
+            let prefix = &encoded[..len];
+            let (lower, upper) = prefix_byte_bounds(prefix).unwrap();
+            assert_eq!(lower.len(), upper.len());
+            let Some((&last_lower, fixed)) = lower.split_last() else {
+                continue;
+            };
+            let last_upper = upper[upper.len() - 1];
+            for last in 0..=255u8 {
+                let data = [fixed, &[last], b"\xaa\x55"].concat();
+                let inside = (last_lower..=last_upper).contains(&last);
+                assert_eq!(crate::base64_encode(&data).starts_with(prefix), inside);
+            }
+        }
+        assert_eq!(prefix_byte_bounds(""), Ok((vec![], vec![])));
+        assert_eq!(
+            prefix_byte_bounds("TW=="),
+            Err(DecodeError::invalid_byte(2, b'='))
+        );
+    }
+
+    #[test]
+    fn prefix_check_reads_only_the_magic() {
+        let png = crate::base64_encode(b"\x89PNG\r\n\x1a\n and the rest of the image");
+        assert_eq!(decode_matches_prefix(&png, b"\x89PNG"), Ok(true));
+        assert_eq!(decode_matches_prefix(&png, b"%PDF"), Ok(false));
+        assert_eq!(decode_matches_prefix("TQ==", b"Man"), Ok(false));
+        assert_eq!(decode_matches_prefix("TWE", b"Ma"), Ok(true));
+
+        // Invalid input past the magic is never reached.
+        assert_eq!(decode_matches_prefix("TWFu$$$$", b"Man"), Ok(true));
+        assert_eq!(
+            decode_matches_prefix("TW$u", b"Man"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+    }
+}
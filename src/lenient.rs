@@ -0,0 +1,262 @@
+//! Opt-in decoders for coping with damaged or sloppy input
+//!
+//! Nothing here is used by the regular decoders. Each function trades
+//! strictness for a better chance of recovering something, so only use
+//! them where that trade-off is acceptable.
+
+use crate::decoder::{decode_with, DecodeState};
+use crate::{Alphabet, Base64Config, DecodeError, Padding, INVALID, PADDING};
+
+/// Maps a character commonly produced by OCR or copy and paste to the
+/// Base64 character it most likely stood for.
+///
+/// Returns `None` for characters that should be dropped.
+fn repair_char(c: char) -> Option<char> {
+    match c {
+        // Line breaks and spacing picked up from the page layout.
+        ' ' | '\t' | '\r' | '\n' | '\u{a0}' => None,
+        // A vertical bar is how OCR usually reads a lowercase L.
+        '|' | '\u{a6}' | '\u{1c0}' => Some('l'),
+        // Fraction and division slashes from typeset documents.
+        '\u{2044}' | '\u{2215}' => Some('/'),
+        // Full-width forms of ASCII, as produced by CJK input methods.
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0),
+        // Cyrillic letters that look identical to Latin ones.
+        '\u{410}' => Some('A'),
+        '\u{412}' => Some('B'),
+        '\u{415}' => Some('E'),
+        '\u{41a}' => Some('K'),
+        '\u{41c}' => Some('M'),
+        '\u{41d}' => Some('H'),
+        '\u{41e}' => Some('O'),
+        '\u{420}' => Some('P'),
+        '\u{421}' => Some('C'),
+        '\u{422}' => Some('T'),
+        '\u{425}' => Some('X'),
+        '\u{430}' => Some('a'),
+        '\u{435}' => Some('e'),
+        '\u{43e}' => Some('o'),
+        '\u{440}' => Some('p'),
+        '\u{441}' => Some('c'),
+        '\u{443}' => Some('y'),
+        '\u{445}' => Some('x'),
+        other => Some(other),
+    }
+}
+
+/// Decodes `data`, and if that fails, retries after undoing common OCR
+/// and pasting damage.
+///
+/// **This is a heuristic and must be opted into deliberately.** The
+/// repaired input may decode to bytes that differ from the original data,
+/// so only use it where a human can confirm the result.
+///
+/// The retry applies exactly these substitutions, in a single pass:
+///
+/// | Found                                        | Replaced by       |
+/// |----------------------------------------------|-------------------|
+/// | space, tab, CR, LF, no-break space           | removed           |
+/// | `\|`, `¦`, `ǀ`                               | `l`               |
+/// | `⁄` (U+2044), `∕` (U+2215)                   | `/`               |
+/// | full-width forms U+FF01 to U+FF5E            | their ASCII form  |
+/// | Cyrillic `А В Е К М Н О Р С Т Х а е о р с у х` | Latin lookalike |
+///
+/// The `O`/`0`, `l`/`1` and `I`/`1` confusions are deliberately not in
+/// the table. All six characters are in the alphabet, and whether a decode
+/// succeeds never depends on which alphabet character a sextet is, so
+/// swapping them cannot turn a failed decode into a successful one: it
+/// could only change the decoded bytes behind the caller's back.
+///
+/// Parameters:
+/// - `data`: The possibly damaged Base64 string.
+///
+/// Returns: The bytes from the first decode that succeeds, or the error
+/// from decoding `data` as is.
+pub fn decode_forgiving(data: &str) -> Result<Vec<u8>, DecodeError> {
+    // Only the error that is returned is reported, once.
+    let err = match decode_with(&Base64Config::STANDARD, data.as_bytes()) {
+        Ok(decoded) => return Ok(decoded),
+        Err(err) => err,
+    };
+
+    let repaired: String = data.chars().filter_map(repair_char).collect();
+    decode_with(&Base64Config::STANDARD, repaired.as_bytes()).map_err(|_| err.report_str(data))
+}
+
+/// Decodes `data` accepting the special characters of both RFC 4648
+/// alphabets, even mixed within one string: `+` and `-` both mean 62,
+/// `/` and `_` both mean 63. Padding may be left out.
+///
+/// **Only use this to cope with broken producers.** Accepting both
+/// alphabets means several different strings decode to the same bytes,
+/// so never use it where the encoded form is compared or signed. It is
+/// `Base64Config::mixed_alphabets` with optional padding.
+///
+/// Example:
+/// ```
+/// use base64::decode_mixed;
+///
+/// assert_eq!(decode_mixed("-+_/").unwrap(), b"\xfb\xef\xff");
+/// ```
+///
+/// Returns: The decoded bytes, or the first `DecodeError` found.
+pub fn decode_mixed(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::new()
+        .mixed_alphabets(true)
+        .padding(Padding::Optional)
+        .decode_str(data)
+}
+
+/// Decodes `data` like `base64_decode`, but on failure also returns the
+/// bytes decoded before it, e.g. to show a user the valid start of a
+/// truncated or corrupted value.
+///
+/// The partial output holds every complete group before the failing one;
+/// the bits of a group cut short by the error are dropped.
+///
+/// Example:
+/// ```
+/// use base64::decode_partial_on_error;
+///
+/// let (err, decoded) = decode_partial_on_error("TWFu$$").unwrap_err();
+/// assert_eq!(err.index(), Some(4));
+/// assert_eq!(decoded, b"Man");
+/// ```
+///
+/// Returns: The decoded bytes, or the `DecodeError` together with the
+/// bytes decoded before it.
+pub fn decode_partial_on_error(data: &str) -> Result<Vec<u8>, (DecodeError, Vec<u8>)> {
+    let config = Base64Config::STANDARD;
+    let input = data.as_bytes();
+    let mut state = DecodeState::new();
+    let mut output = Vec::with_capacity(input.len() / 4 * 3 + 3);
+    let result = input
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, &byte)| state.push(&config, index, byte, &mut output));
+    match result.and_then(|()| state.finish(&config, &mut output)) {
+        Ok(()) => Ok(output),
+        Err(err) => Err((err.report_str(data), output)),
+    }
+}
+
+/// Decodes whatever standard alphabet characters `data` contains,
+/// skipping everything else, and counts what was skipped.
+///
+/// This never fails. Padding is dropped wherever it appears and is not
+/// counted; any other character outside the alphabet, whitespace
+/// included, is skipped and counted. Bits left over at the end that do
+/// not make up a whole byte are dropped.
+///
+/// Example:
+/// ```
+/// use base64::decode_lossy;
+///
+/// assert_eq!(decode_lossy("TW?Fu!eQ*=="), (b"Many".to_vec(), 3));
+/// ```
+///
+/// Returns: The decoded bytes and the number of characters skipped, so
+/// callers can warn about how much of the input was ignored.
+pub fn decode_lossy(data: &str) -> (Vec<u8>, usize) {
+    let mut output = Vec::with_capacity(data.len() / 4 * 3 + 3);
+    let mut skipped = 0;
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in data.chars() {
+        let sextet = match u8::try_from(c) {
+            Ok(byte) => Alphabet::STANDARD.decode_byte(byte),
+            Err(_) => INVALID,
+        };
+        if sextet != INVALID {
+            buffer = (buffer << 6) | u32::from(sextet);
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                output.push((buffer >> bits) as u8);
+            }
+        } else if c != PADDING {
+            skipped += 1;
+        }
+    }
+    (output, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_decode;
+
+    #[test]
+    fn forgiving_recovers_ocr_damage() {
+        // "TG9uZyBsaXZlIGVhc3RlciBlZ2dzIDop" as read back from a scan
+        let damaged = "TG9uZyBs aXZ|IGVh\nc3R\u{ff4c}ciBlZ2dzID\u{43e}p";
+        assert!(base64_decode(damaged).is_err());
+        assert_eq!(
+            decode_forgiving(damaged).unwrap(),
+            b"Long live easter eggs :)"
+        );
+    }
+
+    #[test]
+    fn forgiving_leaves_alphabet_lookalikes_alone() {
+        // `O`, `l` and `I` are valid, so only the space is repaired.
+        assert_eq!(decode_forgiving("TOlI TOlI"), base64_decode("TOlITOlI"));
+        assert_ne!(decode_forgiving("TOlI"), base64_decode("T011"));
+    }
+
+    #[test]
+    fn partial_output_comes_with_the_error() {
+        assert_eq!(
+            decode_partial_on_error("TWFu$$"),
+            Err((DecodeError::invalid_byte(4, b'$'), b"Man".to_vec()))
+        );
+        assert_eq!(
+            decode_partial_on_error("TWFueSBo$WFu"),
+            Err((DecodeError::invalid_byte(8, b'$'), b"Many h".to_vec()))
+        );
+        assert_eq!(
+            decode_partial_on_error("TWFuTQ="),
+            Err((DecodeError::InvalidPadding, b"Man".to_vec()))
+        );
+        assert_eq!(
+            decode_partial_on_error("$"),
+            Err((DecodeError::invalid_byte(0, b'$'), Vec::new()))
+        );
+        assert_eq!(decode_partial_on_error("TWFueQ==").unwrap(), b"Many");
+    }
+
+    #[test]
+    fn forgiving_keeps_clean_results_and_original_errors() {
+        assert_eq!(decode_forgiving("TWFu").unwrap(), b"Man");
+        assert_eq!(
+            decode_forgiving("TW$u"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+    }
+
+    #[test]
+    fn mixed_accepts_both_alphabets_at_once() {
+        let data = b"\xfb\xef\xbe\xff\xff\xfe";
+        let standard = crate::base64_encode(data);
+        let url_safe = crate::base64_encode_urlsafe(data);
+        assert_eq!(standard, "++++///+");
+        assert_eq!(decode_mixed(&standard).unwrap(), data);
+        assert_eq!(decode_mixed(&url_safe).unwrap(), data);
+        assert_eq!(decode_mixed("+-+-_/_+").unwrap(), data);
+        assert!(base64_decode("+-+-_/_+").is_err());
+        assert_eq!(decode_mixed("TWFueQ").unwrap(), b"Many");
+        assert_eq!(
+            decode_mixed("+_.+"),
+            Err(DecodeError::invalid_byte(2, b'.'))
+        );
+    }
+
+    #[test]
+    fn lossy_counts_skipped_characters() {
+        assert_eq!(decode_lossy("TW#Fu\u{e9}ZX\n=="), (b"Mane".to_vec(), 3));
+        assert_eq!(decode_lossy("TWFueQ=="), (b"Many".to_vec(), 0));
+        assert_eq!(decode_lossy("T"), (Vec::new(), 0));
+        assert_eq!(decode_lossy("$$$"), (Vec::new(), 3));
+        assert_eq!(decode_lossy(""), (Vec::new(), 0));
+    }
+}
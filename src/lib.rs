@@ -1,6 +1,102 @@
-/// A Rust implementation of Base64 Encoder and Decoder
+//! A Rust implementation of Base64 Encoder and Decoder
 
-/// The charset and Padding used for encoding and decoding
+mod alphabet;
+mod batch;
+mod channel;
+mod chars;
+mod check;
+mod compare;
+mod compile_time;
+mod config;
+mod decoder;
+mod encoder;
+mod error;
+mod fast;
+mod fingerprint;
+mod framing;
+#[cfg(feature = "hmac")]
+mod hmac;
+mod inspect;
+mod lenient;
+mod lines;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod numeric;
+mod parse;
+mod progress;
+mod simd;
+mod size;
+mod small;
+mod stream;
+#[cfg(feature = "tracing")]
+mod trace;
+mod transcode;
+mod validate;
+mod variants;
+
+pub use alphabet::{Alphabet, AlphabetError, Variant};
+pub use batch::{
+    concat_aligned, decode_batch, decode_btree_map, decode_many, decode_map, decode_multi_record,
+    decode_separated,
+};
+#[cfg(feature = "rayon")]
+pub use batch::decode_many_parallel;
+pub use channel::{channel_encoder, EncodeReceiver, EncodeSender, CHANNEL_BOUND};
+pub use chars::{decode_chars, encode_chars};
+pub use check::{decode_with_check, encode_with_check};
+pub use compare::{decoded_len_eq, semantically_equal};
+pub use compile_time::{const_decode, const_decoded_len};
+pub use config::{Base64Config, InvalidUtf8, LineEnding, Padding};
+pub use decoder::{decode_chunks, decode_hashing, Base64Decoder};
+pub use encoder::{encode_chain, Base64Encoder};
+pub use error::DecodeError;
+#[cfg(feature = "error-context")]
+pub use error::ErrorContext;
+pub use fast::{decode_groups_unchecked, decode_strict_aligned, decode_uninit};
+pub use fingerprint::short_id;
+pub use framing::{
+    decode_and_split, decode_container, decode_quoted, decode_tlv_value, decode_with_prefix,
+    encode_container, encode_strided, encode_with_prefix,
+    CONTAINER_MAGIC,
+};
+#[cfg(feature = "hmac")]
+pub use hmac::{decode_verified, encode_verified};
+pub use inspect::{decode_byte_at, decode_matches_prefix, decode_with_spans, prefix_byte_bounds};
+pub use lenient::{decode_forgiving, decode_lossy, decode_mixed, decode_partial_on_error};
+pub use lines::{
+    decode_openssl, encode_blocks, encode_lines, encode_log_lines, encode_openssl, encode_wrapped,
+    split_radix64_crc,
+};
+#[cfg(feature = "mmap")]
+pub use mmap::encode_mmap;
+pub use numeric::{
+    decode_bits, decode_ipv6, decode_u16, decode_u32, decode_u32_be, decode_u64, decode_u64_be,
+    encode_bits, encode_ipv6, encode_u16, encode_u32, encode_u32_be, encode_u64, encode_u64_be,
+    Endian,
+};
+pub use parse::{decode_then, decode_transform, DecodeThenError};
+pub use progress::{decode_with_progress, encode_with_progress, PROGRESS_INTERVAL};
+pub use simd::{decode_simd, simd_path};
+pub use size::{
+    decoded_len_fast, encoded_len, encoded_len_wrapped, overhead_bytes, overhead_ratio,
+    unpadded_encoded_len,
+};
+pub use small::{decode_small, SmallBytes, INLINE_CAPACITY};
+pub use stream::{
+    decode_byte_results, encode_stream, DecodeIoError, DecoderReader, EncoderWriter, TeeEncoder,
+    DEFAULT_BUFFER_SIZE,
+};
+pub use transcode::{transcode, transcode_stream};
+pub use validate::{encode_once, is_valid_base64, looks_double_encoded, Validator};
+pub use variants::{
+    base64_decode_nopad, base64_decode_urlsafe, base64_decode_urlsafe_nopad, base64_encode_nopad,
+    base64_encode_urlsafe, base64_encode_urlsafe_nopad, decode_detect, decode_url_query,
+    encode_url_query, AlphabetKind,
+};
+
+use std::borrow::Cow;
+
+// The charset and Padding used for encoding and decoding
 
 // This defines the 64 characters used in Base64 encoding.
 const CHARSET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
@@ -9,6 +105,21 @@ const CHARSET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxy
 // when the input data is not a multiple of 3 bytes.
 const PADDING: char = '=';
 
+// Marks bytes that are not part of the charset in `DECODE_TABLE`.
+const INVALID: u8 = 0xFF;
+
+// Reverse lookup from an input byte to its 6-bit charset index,
+// built at compile time from `CHARSET`.
+const DECODE_TABLE: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < CHARSET.len() {
+        table[CHARSET[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
 
 /// Combines two provided bytes into a u16 and collects 6 bits from it using an AND mask
 ///
@@ -47,12 +158,68 @@ fn collect_six_bits(from: (u8, u8), offset: u8) -> u8 {
 /// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
 ///
 /// Returns: A Base64 encoded string.
+///
+/// In debug builds the output is decoded again and checked against
+/// `data` before it is returned. Release builds skip the check.
 pub fn base64_encode(data: &[u8]) -> String {
     let mut encoded_string = String::new();
+    encode_each(data, |c| encoded_string.push(c));
+    check_round_trip(data, &encoded_string);
+    encoded_string
+}
+
+/// Panics in debug builds if `encoded` does not decode back to `data`,
+/// which catches slips in the group and padding arithmetic of
+/// `encode_each` early.
+fn check_round_trip(data: &[u8], encoded: &str) {
+    debug_assert!(
+        base64_decode(encoded).as_deref() == Ok(data),
+        "encoded {} bytes as {:?}, which does not decode back to them",
+        data.len(),
+        encoded
+    );
+}
+
+/// Like `base64_encode`, but returns a borrowed empty string for empty
+/// input instead of allocating, for loops where most inputs are empty.
+///
+/// Example:
+/// ```
+/// use std::borrow::Cow;
+/// use base64::encode_cow;
+///
+/// assert!(matches!(encode_cow(b""), Cow::Borrowed("")));
+/// assert_eq!(encode_cow(b"Man"), "TWFu");
+/// ```
+pub fn encode_cow(data: &[u8]) -> Cow<'static, str> {
+    if data.is_empty() {
+        return Cow::Borrowed("");
+    }
+    Cow::Owned(base64_encode(data))
+}
+
+/// Returns how many padding characters the encoding of `len` bytes ends
+/// with.
+///
+/// Only the bytes of the last, partial group matter:
+///
+/// | `len % 3` | last group      | padding |
+/// |-----------|-----------------|---------|
+/// | 0         | none or 3 bytes | 0       |
+/// | 1         | 1 byte          | 2       |
+/// | 2         | 2 bytes         | 1       |
+const fn padding_needed(len: usize) -> usize {
+    (3 - len % 3) % 3
+}
+
+/// Runs the encoding loop over `data`, handing each output character
+/// (including padding) to `push` in order.
+///
+/// This lets the different output containers share one encoder.
+pub(crate) fn encode_each(data: &[u8], mut push: impl FnMut(char)) {
     let mut bits_encoded = 0usize;
 
-    // Using modulo twice to prevent an underflow   
-    let padding_needed = ((6 - (data.len() * 8) % 6) / 2) % 3;
+    let padding_needed = padding_needed(data.len());
     loop {
         // Integer division
         let lower_byte_index_to_encode = bits_encoded / 8usize;
@@ -69,16 +236,14 @@ pub fn base64_encode(data: &[u8]) -> String {
 
         let bytes_to_encode = (lower_byte_to_encode, upper_byte_to_encode);
         let offset: u8 = (bits_encoded % 8) as u8;
-        encoded_string.push(CHARSET[collect_six_bits(bytes_to_encode, offset) as usize] as char);
+        push(CHARSET[collect_six_bits(bytes_to_encode, offset) as usize] as char);
 
         bits_encoded += 6;
     }
 
     for _ in 0..padding_needed {
-        encoded_string.push(PADDING);
+        push(PADDING);
     }
-
-    encoded_string
 }
 
 /// Base64 decoding converts a Base64 encoded string back into binary data.
@@ -88,27 +253,58 @@ pub fn base64_encode(data: &[u8]) -> String {
 ///
 /// Returns: A `Result` which is:
 /// - `Ok(Vec<u8>)` containing the decoded byte vector on success.
-/// - `Err((&str, u8))` with an error message and invalid byte on failure.
-pub fn base64_decode(data: &str) -> Result<Vec<u8>, (&str, u8)> {
-    let mut collected_bits = 0;
+/// - `Err(DecodeError)` describing the invalid byte or padding on failure.
+///   A non-ASCII character is reported whole as `NonAsciiChar`.
+pub fn base64_decode(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::STANDARD.decode_str(data)
+}
+
+/// Decodes Base64 held in raw bytes, e.g. straight from a network buffer.
+///
+/// Valid Base64 is ASCII, so there is no need to check that `data` is
+/// UTF-8 first; any other byte is reported as `DecodeError::InvalidByte`.
+///
+/// Parameters:
+/// - `data`: The Base64 encoded bytes.
+///
+/// Returns: The same as `base64_decode` for the equivalent string, except
+/// that a non-ASCII character is reported as an `InvalidByte` at its
+/// first byte rather than as a `NonAsciiChar`.
+pub fn base64_decode_bytes(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::STANDARD.decode(data)
+}
+
+/// The decoding loop of the first releases, kept for `Base64Config::legacy`.
+///
+/// It was more permissive than intended: `=` was accepted almost anywhere,
+/// and any number of them just rewound the bit count. The one deliberate
+/// change is that `=` with too few bits before it, as in `====` or `=QUJD`,
+/// is now rejected; the old loop turned it into a run of zero bytes. Don't
+/// use it for anything else.
+pub(crate) fn decode_each(
+    mut databytes: impl Iterator<Item = (usize, u8)>,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut collected_bits = 0u8;
     let mut byte_buffer = 0u16;
-    let mut databytes = data.bytes();
     let mut outputbytes = Vec::<u8>::new();
 
     'decodeloop: loop {
         while collected_bits < 8 {
-            if let Some(nextbyte) = databytes.next() {
+            if let Some((index, nextbyte)) = databytes.next() {
                 // Finds the first occurrence of the latest byte
                 if let Some(idx) = CHARSET.iter().position(|&x| x == nextbyte) {
                     byte_buffer |= ((idx & 0b00111111) as u16) << (10 - collected_bits);
                     collected_bits += 6;
                 } else if nextbyte == (PADDING as u8) {
-                    collected_bits -= 2; // Padding only comes at the end so this works
+                    // Padding without enough bits before it used to
+                    // underflow the count, which release builds wrapped
+                    // around into a run of zero bytes.
+                    if collected_bits < 2 {
+                        return Err(DecodeError::InvalidPadding);
+                    }
+                    collected_bits -= 2;
                 } else {
-                    return Err((
-                        "Failed to decode base64: Expected byte from charset, found invalid byte.",
-                        nextbyte,
-                    ));
+                    return Err(DecodeError::invalid_byte(index, nextbyte));
                 }
             } else {
                 break 'decodeloop;
@@ -121,7 +317,7 @@ pub fn base64_decode(data: &str) -> Result<Vec<u8>, (&str, u8)> {
     }
 
     if collected_bits != 0 {
-        return Err(("Failed to decode base64: Invalid padding.", collected_bits));
+        return Err(DecodeError::InvalidPadding);
     }
 
     Ok(outputbytes)
@@ -131,6 +327,35 @@ pub fn base64_decode(data: &str) -> Result<Vec<u8>, (&str, u8)> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn padding_needed_matches_the_old_formula() {
+        for len in 0..300usize {
+            assert_eq!(padding_needed(len), ((6 - (len * 8) % 6) / 2) % 3);
+        }
+        // `usize::MAX` is a multiple of 3 on both 32 and 64 bit targets.
+        assert_eq!(padding_needed(usize::MAX), 0);
+        assert_eq!(padding_needed(usize::MAX - 1), 1);
+    }
+
+    #[test]
+    fn round_trip_check_passes_valid_encodes_unchanged() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for len in 0..data.len() {
+            assert_eq!(
+                base64_encode(&data[..len]),
+                Base64Config::STANDARD.encode(&data[..len])
+            );
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "does not decode back")]
+    fn round_trip_check_catches_bad_encodes() {
+        // What an encoder that forgot the last partial group would return.
+        check_round_trip(b"Many", "TWFu");
+    }
+
     #[test]
     fn pregenerated_random_bytes_encode() {
         macro_rules! test_encode {
@@ -300,4 +525,51 @@ mod tests {
         test_d_e!("SGFwcHkgSGFja3RvYmVyZmVzdCE=");
         test_d_e!("PVRoZSBBbGdvcml0aG1zPQ==");
     }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(
+            base64_decode("TWF$"),
+            Err(DecodeError::invalid_byte(3, b'$'))
+        );
+        assert_eq!(base64_decode("TWF"), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    fn encode_cow_borrows_only_when_empty() {
+        assert!(matches!(encode_cow(b""), Cow::Borrowed("")));
+        for data in [&b"M"[..], b"Ma", b"Man", b"Many hands"] {
+            let encoded = encode_cow(data);
+            assert!(matches!(encoded, Cow::Owned(_)));
+            assert_eq!(encoded, base64_encode(data));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn padding_alone_is_rejected() {
+        let optional = Base64Config::new().padding(Padding::Optional);
+        for input in ["=", "==", "===", "====", "========", "TWFu===="] {
+            assert_eq!(base64_decode(input), Err(DecodeError::InvalidPadding));
+            assert_eq!(
+                optional.decode(input.as_bytes()),
+                Err(DecodeError::InvalidPadding)
+            );
+            assert_eq!(
+                Base64Config::legacy().decode(input.as_bytes()),
+                Err(DecodeError::InvalidPadding)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_bytes_matches_decode() {
+        for input in ["", "TWFu", "SGFwcHkgSGFja3RvYmVyZmVzdCE=", "TWF$", "TWF"] {
+            assert_eq!(base64_decode_bytes(input.as_bytes()), base64_decode(input));
+        }
+        assert_eq!(
+            base64_decode_bytes(b"TW\xff="),
+            Err(DecodeError::invalid_byte(2, 0xff))
+        );
+    }
 }
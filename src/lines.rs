@@ -0,0 +1,338 @@
+//! Line oriented Base64 output
+
+use crate::{base64_encode, Base64Config, Base64Encoder, DecodeError, Padding};
+
+/// Encodes `data` and splits the output into lines of `line_width`
+/// characters.
+///
+/// Every line but the last is exactly `line_width` long; the last one may
+/// be shorter and carries the padding. A `line_width` of 0 means no
+/// wrapping, giving a single line.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `line_width`: The number of characters per line.
+///
+/// Returns: The lines in order, without line terminators. Empty input
+/// gives no lines.
+pub fn encode_lines(data: &[u8], line_width: usize) -> Vec<String> {
+    split_lines(base64_encode(data), line_width)
+}
+
+/// Splits encoded output into lines as described on `encode_lines`.
+fn split_lines(encoded: String, line_width: usize) -> Vec<String> {
+    if encoded.is_empty() {
+        return Vec::new();
+    }
+    if line_width == 0 {
+        return vec![encoded];
+    }
+
+    // Base64 output is ASCII, so splitting on bytes is always valid UTF-8.
+    encoded
+        .as_bytes()
+        .chunks(line_width)
+        .map(|line| String::from_utf8(line.to_vec()).expect("Base64 output is ASCII"))
+        .collect()
+}
+
+/// Encodes `data` as a single string wrapped every `line_width`
+/// characters with `\n`, the form expected by many text formats.
+///
+/// The lines are the ones `encode_lines` returns; there is no trailing
+/// newline. A `line_width` of 0 means no wrapping.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `line_width`: The number of characters per line.
+///
+/// Returns: The wrapped Base64 encoded string.
+pub fn encode_wrapped(data: &[u8], line_width: usize) -> String {
+    let encoded = base64_encode(data);
+    if line_width == 0 {
+        return encoded;
+    }
+
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / line_width);
+    for (i, c) in encoded.chars().enumerate() {
+        if i > 0 && i % line_width == 0 {
+            out.push('\n');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The line width of `openssl base64` and `openssl enc -base64`.
+const OPENSSL_LINE_WIDTH: usize = 64;
+
+/// Encodes `data` exactly like `openssl base64` without `-A`.
+///
+/// That is padded standard Base64 in lines of 64 characters, each one,
+/// the last included, ended by `\n`. Empty input gives an empty string.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+///
+/// Returns: The encoded text, byte for byte what OpenSSL writes.
+pub fn encode_openssl(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() / 3 * 4 + data.len() / 48 + 6);
+    for line in encode_lines(data, OPENSSL_LINE_WIDTH) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Decodes the output of `openssl base64`, wrapped or not.
+///
+/// Like `openssl base64 -d`, this takes padded standard Base64 and skips
+/// all whitespace, so lines of any width, `\r\n` line ends and the
+/// trailing newline are all accepted.
+///
+/// Returns: The decoded bytes, or the first `DecodeError` found.
+pub fn decode_openssl(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::new().allow_whitespace(true).decode_str(data)
+}
+
+/// Splits the body of an OpenPGP armored block into its data and the
+/// CRC-24 from its trailing `=XXXX` checksum line (RFC 4880 §6.1).
+///
+/// `body` is everything between the header and the `-----END` line.
+/// Trailing line breaks are ignored, and the data is returned without
+/// the line break before the checksum, ready for `decode_openssl`. The
+/// CRC itself is not checked against the data here.
+///
+/// Example:
+/// ```
+/// use base64::split_radix64_crc;
+///
+/// let body = "TWFueSBoYW5kcyBtYWtlIGxpZ2h0IHdvcmsuCg==\n=lzFI\n";
+/// let (data, crc) = split_radix64_crc(body).unwrap();
+/// assert_eq!(data, "TWFueSBoYW5kcyBtYWtlIGxpZ2h0IHdvcmsuCg==");
+/// assert_eq!(crc, 0x97_3148);
+/// ```
+///
+/// Returns: The data and the CRC, or:
+/// - `MissingChecksum` if the last line does not start with `=`.
+/// - `InvalidLength` holding the checksum's length if it is not 4
+///   characters.
+/// - The error from decoding the checksum, indexed into `body`.
+pub fn split_radix64_crc(body: &str) -> Result<(&str, u32), DecodeError> {
+    let body = body.trim_end_matches(['\r', '\n']);
+    let line_start = body.rfind('\n').map_or(0, |i| i + 1);
+    let checksum = match body[line_start..].strip_prefix('=') {
+        Some(checksum) => checksum,
+        None => return Err(DecodeError::MissingChecksum),
+    };
+    if checksum.len() != 4 {
+        return Err(DecodeError::InvalidLength {
+            len: checksum.len(),
+        });
+    }
+    let crc = Base64Config::new()
+        .padding(Padding::Forbidden)
+        .decode_str(checksum)
+        .map_err(|err| err.shifted(line_start + 1))?;
+    let data = body[..line_start].trim_end_matches(['\r', '\n']);
+    Ok((data, u32::from_be_bytes([0, crc[0], crc[1], crc[2]])))
+}
+
+/// How many input bytes `encode_log_lines` collects before encoding them.
+const LOG_CHUNK: usize = 3 * 256;
+
+/// Encodes `bytes` into lines of `width` characters, each starting with
+/// `prefix` and a space, e.g. `blob: TWFueSBo`, for dumping binary data
+/// into logs.
+///
+/// Because every line carries the prefix, the lines of one dump can be
+/// grepped out of interleaved logs; stripping the prefixes and joining
+/// the lines gives back the encoding. An empty `prefix` gives bare lines.
+/// A `width` of 0 means no wrapping, giving a single line.
+///
+/// Parameters:
+/// - `bytes`: The data to be encoded, from any byte iterator.
+/// - `prefix`: Written at the start of every line.
+/// - `width`: The number of Base64 characters per line.
+///
+/// Returns: The lines in order, without line terminators. Empty input
+/// gives no lines.
+pub fn encode_log_lines<I: IntoIterator<Item = u8>>(
+    bytes: I,
+    prefix: &str,
+    width: usize,
+) -> Vec<String> {
+    let mut encoder = Base64Encoder::new();
+    let mut encoded = String::new();
+    let mut pending = Vec::with_capacity(LOG_CHUNK);
+    for byte in bytes {
+        pending.push(byte);
+        if pending.len() == LOG_CHUNK {
+            encoder.update_into(&pending, &mut encoded);
+            pending.clear();
+        }
+    }
+    encoder.update_into(&pending, &mut encoded);
+    encoder.finalize_into(&mut encoded);
+
+    let lines = split_lines(encoded, width);
+    if prefix.is_empty() {
+        return lines;
+    }
+    lines
+        .into_iter()
+        .map(|line| format!("{} {}", prefix, line))
+        .collect()
+}
+
+/// Encodes `data` as a sequence of fixed-size blocks, for transports that
+/// send Base64 in frames of `block_chars` characters.
+///
+/// Each block encodes its own `block_chars / 4 * 3` input bytes, so blocks
+/// can be decoded independently. Every block but the last is exactly
+/// `block_chars` long; the last one may be shorter and carries the padding.
+///
+/// Panics if `block_chars` is not a positive multiple of 4, since only
+/// whole groups can be encoded on their own.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `block_chars`: The number of characters per block.
+///
+/// Returns: The blocks in order. Empty input gives no blocks.
+pub fn encode_blocks(data: &[u8], block_chars: usize) -> Vec<String> {
+    assert!(
+        block_chars > 0 && block_chars.is_multiple_of(4),
+        "block size must be a positive multiple of 4, got {}",
+        block_chars
+    );
+    data.chunks(block_chars / 4 * 3)
+        .map(base64_encode)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_join_to_one_shot_encode() {
+        let data: Vec<u8> = (0..100u8).collect();
+        for width in [1, 4, 7, 64, 76, 500] {
+            let lines = encode_lines(&data, width);
+            assert_eq!(lines.concat(), base64_encode(&data));
+            let (last, full) = lines.split_last().unwrap();
+            assert!(full.iter().all(|line| line.len() == width));
+            assert!(!last.is_empty() && last.len() <= width);
+        }
+    }
+
+    #[test]
+    fn lines_edge_cases() {
+        assert!(encode_lines(b"", 4).is_empty());
+        assert_eq!(encode_lines(b"Many hands", 0), ["TWFueSBoYW5kcw=="]);
+        assert_eq!(encode_lines(b"Many hands", 6), ["TWFueS", "BoYW5k", "cw=="]);
+    }
+
+    #[test]
+    fn lines_join_to_wrapped_encode() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for width in [0, 1, 3, 4, 64, 76, 400] {
+            assert_eq!(
+                encode_lines(&data, width).join("\n"),
+                encode_wrapped(&data, width)
+            );
+        }
+        assert_eq!(encode_wrapped(b"Many hands", 6), "TWFueS\nBoYW5k\ncw==");
+        assert_eq!(encode_wrapped(b"", 6), "");
+    }
+
+    #[test]
+    fn openssl_output_matches_byte_for_byte() {
+        // Captured from `printf '...' | openssl base64` with OpenSSL 3.5.
+        let data = b"The quick brown fox jumps over the lazy dog, and then some more text to wrap.";
+        let openssl = "VGhlIHF1aWNrIGJyb3duIGZveCBqdW1wcyBvdmVyIHRoZSBsYXp5IGRvZywgYW5k\n\
+                       IHRoZW4gc29tZSBtb3JlIHRleHQgdG8gd3JhcC4=\n";
+        assert_eq!(encode_openssl(data), openssl);
+        assert_eq!(decode_openssl(openssl).unwrap(), data);
+
+        // 48 bytes fill exactly one line, which still gets its newline.
+        let full_line = encode_openssl(&[0; 48]);
+        assert_eq!(full_line, format!("{}\n", "A".repeat(64)));
+        assert_eq!(encode_openssl(b""), "");
+
+        assert_eq!(decode_openssl("TWFu\r\n  eQ==\r\n").unwrap(), b"Many");
+        assert!(decode_openssl("TWFueQ").is_err());
+    }
+
+    #[test]
+    fn log_lines_strip_back_to_one_shot_encode() {
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 7) as u8).collect();
+        for width in [0, 4, 64, 77] {
+            let lines = encode_log_lines(data.iter().copied(), "blob:", width);
+            let stripped: Vec<&str> = lines
+                .iter()
+                .map(|line| line.strip_prefix("blob: ").unwrap())
+                .collect();
+            assert_eq!(stripped, encode_lines(&data, width));
+        }
+        assert_eq!(
+            encode_log_lines(*b"Many hands", "", 8),
+            ["TWFueSBo", "YW5kcw=="]
+        );
+        assert!(encode_log_lines(std::iter::empty(), "blob:", 64).is_empty());
+    }
+
+    #[test]
+    fn blocks_are_fixed_size_and_join_to_one_shot_encode() {
+        let data: Vec<u8> = (0..100u8).collect();
+        for block in [4, 8, 64, 400, 1000] {
+            let blocks = encode_blocks(&data, block);
+            assert_eq!(blocks.concat(), base64_encode(&data));
+            let (last, full) = blocks.split_last().unwrap();
+            assert!(full.iter().all(|b| b.len() == block));
+            assert!(last.len() <= block);
+        }
+        assert!(encode_blocks(b"", 8).is_empty());
+    }
+
+    #[test]
+    fn radix64_crc_is_split_off() {
+        // The tail of `gpg --enarmor` output for "Many hands make light work.\n"
+        let body = "TWFueSBoYW5kcyBtYWtlIGxpZ2h0IHdvcmsuCg==\r\n=lzFI\r\n";
+        let (data, crc) = split_radix64_crc(body).unwrap();
+        assert_eq!(
+            decode_openssl(data).unwrap(),
+            b"Many hands make light work.\n"
+        );
+        assert_eq!(crc, 0x97_3148);
+        assert_eq!(split_radix64_crc("=AAAA").unwrap(), ("", 0));
+    }
+
+    #[test]
+    fn radix64_crc_must_be_present_and_well_formed() {
+        assert_eq!(
+            split_radix64_crc("TWFueQ==\n"),
+            Err(DecodeError::MissingChecksum)
+        );
+        assert_eq!(split_radix64_crc(""), Err(DecodeError::MissingChecksum));
+        assert_eq!(
+            split_radix64_crc("TWFu\n=lzF"),
+            Err(DecodeError::InvalidLength { len: 3 })
+        );
+        assert_eq!(
+            split_radix64_crc("TWFu\n=lz$I"),
+            Err(DecodeError::invalid_byte(8, b'$'))
+        );
+        assert_eq!(
+            split_radix64_crc("TWFu\n=lz=="),
+            Err(DecodeError::invalid_byte(8, b'='))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple of 4")]
+    fn block_size_must_be_whole_groups() {
+        encode_blocks(b"abc", 6);
+    }
+}
@@ -14,8 +14,8 @@ fn main() {
             let decoded_str = String::from_utf8(decoded).expect("Invalid UTF-8 sequence");
             println!("Decoded: {}", decoded_str);
         },
-        Err((err_msg, invalid_byte)) => {
-            println!("Error: {} (invalid byte: {})", err_msg, invalid_byte);
+        Err(err) => {
+            println!("Error: {}", err);
         }
     }
 }
@@ -0,0 +1,75 @@
+//! Encoding files through a memory map
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::EncoderWriter;
+
+/// Encodes the file at `path` to `out` without reading it into memory.
+///
+/// The file is memory mapped and fed through an [`EncoderWriter`], so only
+/// its buffer is held in RAM however large the file is.
+///
+/// # Safety
+///
+/// No process may truncate or write to the file until this returns. The
+/// mapped bytes are read as an ordinary `&[u8]`, so a concurrent write is
+/// undefined behaviour, and truncation can kill the process with `SIGBUS`.
+///
+/// Parameters:
+/// - `path`: The file to encode.
+/// - `out`: Where the padded standard Base64 is written.
+///
+/// Returns: An I/O error if the file cannot be opened or mapped, or if
+/// writing to `out` fails.
+pub unsafe fn encode_mmap(path: &Path, out: &mut impl Write) -> io::Result<()> {
+    let file = File::open(path)?;
+    // SAFETY: the caller guarantees the file is not modified while mapped.
+    let mapping = unsafe { Mmap::map(&file)? };
+    let mut writer = EncoderWriter::new(&mut *out);
+    writer.write_all(&mapping)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_encode, DEFAULT_BUFFER_SIZE};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn mapped_file_encodes_like_one_shot() {
+        let data: Vec<u8> = (0..DEFAULT_BUFFER_SIZE * 12 + 1)
+            .map(|i| (i * 7 + i / 251) as u8)
+            .collect();
+        let path = std::env::temp_dir().join(format!("base64-mmap-{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+
+        let mut out = Vec::new();
+        // SAFETY: the files are private to this test.
+        let result = unsafe { encode_mmap(&path, &mut out) };
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+        assert_eq!(hash(&out), hash(base64_encode(&data).as_bytes()));
+
+        let empty = std::env::temp_dir().join(format!("base64-mmap-empty-{}", std::process::id()));
+        std::fs::write(&empty, b"").unwrap();
+        let mut out = Vec::new();
+        // SAFETY: as above.
+        let result = unsafe { encode_mmap(&empty, &mut out) };
+        std::fs::remove_file(&empty).unwrap();
+        result.unwrap();
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+//! Encoding numeric values through their byte representation
+
+use std::net::Ipv6Addr;
+
+use crate::{base64_decode, base64_encode, Base64Config, DecodeError};
+
+/// The byte order used to serialize a single integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// Most significant byte first, i.e. network order.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Decodes `data`, which must hold exactly `N` bytes.
+fn decode_exact<const N: usize>(data: &str) -> Result<[u8; N], DecodeError> {
+    let bytes = base64_decode(data)?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| DecodeError::LengthMismatch {
+            expected: N,
+            actual: bytes.len(),
+        })
+}
+
+/// Encodes `value` as 2 bytes in the given byte order.
+pub fn encode_u16(value: u16, endian: Endian) -> String {
+    match endian {
+        Endian::Big => base64_encode(&value.to_be_bytes()),
+        Endian::Little => base64_encode(&value.to_le_bytes()),
+    }
+}
+
+/// Decodes the output of `encode_u16` back into the value.
+///
+/// Returns: The value, or `DecodeError::LengthMismatch` if `data` does not
+/// decode to exactly 2 bytes.
+pub fn decode_u16(data: &str, endian: Endian) -> Result<u16, DecodeError> {
+    let bytes = decode_exact(data)?;
+    Ok(match endian {
+        Endian::Big => u16::from_be_bytes(bytes),
+        Endian::Little => u16::from_le_bytes(bytes),
+    })
+}
+
+/// Encodes `value` as 4 bytes in the given byte order.
+pub fn encode_u32(value: u32, endian: Endian) -> String {
+    match endian {
+        Endian::Big => base64_encode(&value.to_be_bytes()),
+        Endian::Little => base64_encode(&value.to_le_bytes()),
+    }
+}
+
+/// Decodes the output of `encode_u32` back into the value.
+///
+/// Returns: The value, or `DecodeError::LengthMismatch` if `data` does not
+/// decode to exactly 4 bytes.
+pub fn decode_u32(data: &str, endian: Endian) -> Result<u32, DecodeError> {
+    let bytes = decode_exact(data)?;
+    Ok(match endian {
+        Endian::Big => u32::from_be_bytes(bytes),
+        Endian::Little => u32::from_le_bytes(bytes),
+    })
+}
+
+/// Encodes `value` as 8 bytes in the given byte order, e.g. for a counter
+/// or ID.
+pub fn encode_u64(value: u64, endian: Endian) -> String {
+    match endian {
+        Endian::Big => base64_encode(&value.to_be_bytes()),
+        Endian::Little => base64_encode(&value.to_le_bytes()),
+    }
+}
+
+/// Decodes the output of `encode_u64` back into the value.
+///
+/// Returns: The value, or `DecodeError::LengthMismatch` if `data` does not
+/// decode to exactly 8 bytes.
+pub fn decode_u64(data: &str, endian: Endian) -> Result<u64, DecodeError> {
+    let bytes = decode_exact(data)?;
+    Ok(match endian {
+        Endian::Big => u64::from_be_bytes(bytes),
+        Endian::Little => u64::from_le_bytes(bytes),
+    })
+}
+
+/// Encodes the 16 bytes of `addr` as a compact URL-safe token.
+///
+/// The token has no padding and is always 22 characters long.
+///
+/// Example:
+/// ```
+/// use base64::encode_ipv6;
+/// use std::net::Ipv6Addr;
+///
+/// assert_eq!(encode_ipv6(Ipv6Addr::LOCALHOST), "AAAAAAAAAAAAAAAAAAAAAQ");
+/// ```
+pub fn encode_ipv6(addr: Ipv6Addr) -> String {
+    Base64Config::URL_SAFE_NO_PAD.encode(&addr.octets())
+}
+
+/// Decodes the output of `encode_ipv6` back into the address.
+///
+/// Returns: The address, or `DecodeError::LengthMismatch` if `data` does
+/// not decode to exactly 16 bytes.
+pub fn decode_ipv6(data: &str) -> Result<Ipv6Addr, DecodeError> {
+    let bytes = Base64Config::URL_SAFE_NO_PAD.decode_str(data)?;
+    let octets: [u8; 16] =
+        bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| DecodeError::LengthMismatch {
+                expected: 16,
+                actual: bytes.len(),
+            })?;
+    Ok(Ipv6Addr::from(octets))
+}
+
+/// Decodes `data` and splits the bytes into chunks of `N`.
+fn decode_elements<const N: usize>(data: &str) -> Result<Vec<[u8; N]>, DecodeError> {
+    let bytes = base64_decode(data)?;
+    if !bytes.len().is_multiple_of(N) {
+        return Err(DecodeError::InvalidLength { len: bytes.len() });
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| chunk.try_into().expect("chunk has N bytes"))
+        .collect())
+}
+
+/// Encodes each value as 4 big-endian bytes, then encodes the
+/// concatenation.
+pub fn encode_u32_be(values: &[u32]) -> String {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    base64_encode(&bytes)
+}
+
+/// Decodes the output of `encode_u32_be` back into values.
+///
+/// Returns: The values, or `DecodeError::InvalidLength` holding the
+/// decoded byte count if it is not a multiple of 4.
+pub fn decode_u32_be(data: &str) -> Result<Vec<u32>, DecodeError> {
+    Ok(decode_elements(data)?
+        .into_iter()
+        .map(u32::from_be_bytes)
+        .collect())
+}
+
+/// Encodes each value as 8 big-endian bytes, then encodes the
+/// concatenation.
+pub fn encode_u64_be(values: &[u64]) -> String {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    base64_encode(&bytes)
+}
+
+/// Decodes the output of `encode_u64_be` back into values.
+///
+/// Returns: The values, or `DecodeError::InvalidLength` holding the
+/// decoded byte count if it is not a multiple of 8.
+pub fn decode_u64_be(data: &str) -> Result<Vec<u64>, DecodeError> {
+    Ok(decode_elements(data)?
+        .into_iter()
+        .map(u64::from_be_bytes)
+        .collect())
+}
+
+/// Packs `flags` 8 to a byte, most significant bit first, and encodes
+/// the bytes.
+///
+/// A final partial byte is filled up with zero bits, so the number of
+/// flags has to be known again to decode them.
+///
+/// Example:
+/// ```
+/// use base64::encode_bits;
+///
+/// assert_eq!(encode_bits(&[true, false, true]), "oA==");
+/// ```
+pub fn encode_bits(flags: &[bool]) -> String {
+    let bytes: Vec<u8> = flags
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &flag)| byte | (u8::from(flag) << (7 - i)))
+        })
+        .collect();
+    base64_encode(&bytes)
+}
+
+/// Decodes the output of `encode_bits` back into `count` flags.
+///
+/// Returns: The flags, or `DecodeError::LengthMismatch` if `data` does not
+/// decode to exactly the bytes that `count` flags pack into.
+pub fn decode_bits(data: &str, count: usize) -> Result<Vec<bool>, DecodeError> {
+    let bytes = base64_decode(data)?;
+    if bytes.len() != count.div_ceil(8) {
+        return Err(DecodeError::LengthMismatch {
+            expected: count.div_ceil(8),
+            actual: bytes.len(),
+        });
+    }
+    Ok((0..count)
+        .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_vectors_round_trip() {
+        assert_eq!(encode_u32_be(&[]), "");
+        assert_eq!(decode_u32_be("").unwrap(), Vec::<u32>::new());
+        assert_eq!(decode_u64_be("").unwrap(), Vec::<u64>::new());
+
+        let ids = [0, 1, 0xdead_beef, u32::MAX];
+        assert_eq!(encode_u32_be(&[0x4d61_6e00]), "TWFuAA==");
+        assert_eq!(decode_u32_be(&encode_u32_be(&ids)).unwrap(), ids);
+
+        let big = [0, 42, u64::MAX, 0x0123_4567_89ab_cdef];
+        assert_eq!(decode_u64_be(&encode_u64_be(&big)).unwrap(), big);
+    }
+
+    #[test]
+    fn numeric_decode_checks_element_size() {
+        assert_eq!(
+            decode_u32_be("TWFu"),
+            Err(DecodeError::InvalidLength { len: 3 })
+        );
+        assert_eq!(
+            decode_u64_be(&encode_u32_be(&[1, 2, 3])),
+            Err(DecodeError::InvalidLength { len: 12 })
+        );
+    }
+
+    #[test]
+    fn scalars_round_trip_in_both_byte_orders() {
+        assert_eq!(encode_u32(0x4d61_6e00, Endian::Big), "TWFuAA==");
+        assert_eq!(encode_u32(0x006e_614d, Endian::Little), "TWFuAA==");
+        assert_eq!(encode_u16(1, Endian::Big), "AAE=");
+        assert_eq!(encode_u16(1, Endian::Little), "AQA=");
+
+        for endian in [Endian::Big, Endian::Little] {
+            for value in [0, 1, 0x0123_4567_89ab_cdef, u64::MAX] {
+                assert_eq!(decode_u64(&encode_u64(value, endian), endian), Ok(value));
+            }
+            for value in [0, 0xdead_beef, u32::MAX] {
+                assert_eq!(decode_u32(&encode_u32(value, endian), endian), Ok(value));
+            }
+            assert_eq!(decode_u16(&encode_u16(0xbeef, endian), endian), Ok(0xbeef));
+        }
+        assert_eq!(
+            decode_u64(&encode_u64(7, Endian::Big), Endian::Little),
+            Ok(7 << 56)
+        );
+    }
+
+    #[test]
+    fn scalar_decode_requires_the_exact_size() {
+        assert_eq!(
+            decode_u64(&encode_u32(7, Endian::Big), Endian::Big),
+            Err(DecodeError::LengthMismatch {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            decode_u16("", Endian::Little),
+            Err(DecodeError::LengthMismatch {
+                expected: 2,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn ipv6_round_trips_as_22_characters() {
+        let full = Ipv6Addr::new(0x2001, 0xdb8, 0x85a3, 0xffff, 0xfbff, 0x8a2e, 0x370, 0x7334);
+        for addr in [Ipv6Addr::LOCALHOST, Ipv6Addr::UNSPECIFIED, full] {
+            let token = encode_ipv6(addr);
+            assert_eq!(token.len(), 22);
+            assert_eq!(decode_ipv6(&token), Ok(addr));
+        }
+        assert_eq!(encode_ipv6(full), "IAENuIWj___7_4ouA3BzNA");
+        assert_eq!(
+            decode_ipv6(&encode_u64(1, Endian::Big).replace('=', "")),
+            Err(DecodeError::LengthMismatch {
+                expected: 16,
+                actual: 8
+            })
+        );
+    }
+
+    #[test]
+    fn bits_round_trip() {
+        let flags = [
+            true, false, false, true, true, true, false, true, false, true,
+        ];
+        let encoded = encode_bits(&flags);
+        assert_eq!(encoded, "nUA=");
+        assert_eq!(decode_bits(&encoded, flags.len()).unwrap(), flags);
+        assert_eq!(decode_bits(&encoded, 16).unwrap()[..10], flags);
+        assert_eq!(
+            decode_bits(&encoded, 17),
+            Err(DecodeError::LengthMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+        assert_eq!(encode_bits(&[]), "");
+        assert_eq!(decode_bits("", 0).unwrap(), Vec::<bool>::new());
+    }
+}
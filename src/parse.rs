@@ -0,0 +1,126 @@
+//! Decoding straight into a parsed value
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{base64_decode, DecodeError};
+
+/// The error of `decode_then` and `decode_transform`: either the input
+/// was not valid Base64, or the parser or transform rejected the decoded
+/// bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeThenError<E> {
+    /// The input could not be decoded.
+    Decode(DecodeError),
+    /// The decoded bytes could not be parsed or transformed.
+    Parse(E),
+}
+
+impl<E: fmt::Display> fmt::Display for DecodeThenError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeThenError::Decode(err) => err.fmt(f),
+            DecodeThenError::Parse(err) => write!(f, "Failed to parse decoded base64: {}", err),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for DecodeThenError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeThenError::Decode(err) => Some(err),
+            DecodeThenError::Parse(err) => Some(err),
+        }
+    }
+}
+
+/// Decodes `data` and hands the bytes to `parse`, e.g. to deserialize a
+/// token.
+///
+/// Example:
+/// ```
+/// use base64::decode_then;
+///
+/// let value = decode_then("KgAAAA==", |bytes| <[u8; 4]>::try_from(bytes).map(u32::from_le_bytes));
+/// assert_eq!(value.unwrap(), 42);
+/// ```
+///
+/// Parameters:
+/// - `data`: A Base64 encoded string.
+/// - `parse`: Turns the decoded bytes into the value.
+///
+/// Returns: The parsed value, or a `DecodeThenError` saying which of the
+/// two steps failed.
+pub fn decode_then<T, E, F>(data: &str, parse: F) -> Result<T, DecodeThenError<E>>
+where
+    F: FnOnce(&[u8]) -> Result<T, E>,
+{
+    let decoded = base64_decode(data).map_err(DecodeThenError::Decode)?;
+    parse(&decoded).map_err(DecodeThenError::Parse)
+}
+
+/// Decodes `data` and passes the bytes through `transform`, e.g. to
+/// decompress a Base64 encoded gzip payload.
+///
+/// Unlike `decode_then`, the transform takes ownership of the decoded
+/// buffer, so it can reuse it or return it unchanged for free. Any
+/// decompressor can be plugged in; this crate depends on none.
+///
+/// Parameters:
+/// - `data`: A Base64 encoded string.
+/// - `transform`: Turns the decoded bytes into the final bytes.
+///
+/// Returns: The transformed bytes, or a `DecodeThenError` saying which of
+/// the two steps failed.
+pub fn decode_transform<E, F>(data: &str, transform: F) -> Result<Vec<u8>, DecodeThenError<E>>
+where
+    F: FnOnce(Vec<u8>) -> Result<Vec<u8>, E>,
+{
+    let decoded = base64_decode(data).map_err(DecodeThenError::Decode)?;
+    transform(decoded).map_err(DecodeThenError::Parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::array::TryFromSliceError;
+
+    fn le_u32(bytes: &[u8]) -> Result<u32, TryFromSliceError> {
+        <[u8; 4]>::try_from(bytes).map(u32::from_le_bytes)
+    }
+
+    #[test]
+    fn decoded_bytes_are_parsed() {
+        assert_eq!(decode_then("eFY0Eg==", le_u32).unwrap(), 0x1234_5678);
+    }
+
+    #[test]
+    fn both_failures_are_told_apart() {
+        match decode_then("eFY0E!==", le_u32) {
+            Err(DecodeThenError::Decode(err)) => {
+                assert_eq!(err, DecodeError::invalid_byte(5, b'!'))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let err = decode_then("eFY0", le_u32).unwrap_err();
+        assert!(matches!(err, DecodeThenError::Parse(_)));
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse decoded base64"));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn transform_sees_the_decoded_bytes() {
+        let identity = decode_transform("TWFu", Ok::<_, String>);
+        assert_eq!(identity.unwrap(), b"Man");
+
+        let failing = decode_transform("TWFu", |bytes| Err(format!("{} bytes", bytes.len())));
+        assert_eq!(failing, Err(DecodeThenError::Parse("3 bytes".to_string())));
+        assert_eq!(
+            decode_transform("TW!u", Ok::<_, String>),
+            Err(DecodeThenError::Decode(DecodeError::invalid_byte(2, b'!')))
+        );
+    }
+}
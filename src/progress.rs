@@ -0,0 +1,183 @@
+//! Encoding and decoding with progress reporting
+
+use crate::decoder::DecodeState;
+use crate::fast::for_each_group;
+use crate::{Base64Config, Base64Encoder, DecodeError, PADDING};
+
+/// How many input bytes are processed between two progress callbacks.
+pub const PROGRESS_INTERVAL: usize = 64 * 1024;
+
+/// Encodes `data` like `base64_encode`, reporting progress along the way.
+///
+/// `progress` is called with `(bytes_processed, total)` after every
+/// `PROGRESS_INTERVAL` input bytes, and always once at the end with
+/// `bytes_processed == total`, even for empty input.
+///
+/// Parameters:
+/// - `data`: A byte slice (`&[u8]`) of the data to be encoded.
+/// - `progress`: The callback receiving `(bytes_processed, total)`.
+///
+/// Returns: A Base64 encoded string.
+pub fn encode_with_progress<F: FnMut(usize, usize)>(data: &[u8], mut progress: F) -> String {
+    let mut encoder = Base64Encoder::new();
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    let mut processed = 0;
+
+    for chunk in data.chunks(PROGRESS_INTERVAL) {
+        encoded.push_str(&encoder.update(chunk));
+        processed += chunk.len();
+        progress(processed, data.len());
+    }
+    if data.is_empty() {
+        progress(0, 0);
+    }
+
+    encoded.push_str(&encoder.finalize());
+    encoded
+}
+
+/// Decodes `data` like `base64_decode`, reporting progress along the way.
+///
+/// `progress` is called with `(bytes_processed, total)` in input bytes,
+/// on the same schedule as `encode_with_progress`. No call is made for
+/// the input after an error.
+///
+/// Each interval but the last is decoded by the group at a time decoder
+/// of `decode_strict_aligned`. Only the last one, and any interval that
+/// holds padding or a bad byte, goes through the byte at a time decoder
+/// of `base64_decode`, which also finds the exact error.
+///
+/// Parameters:
+/// - `data`: The Base64 encoded string.
+/// - `progress`: The callback receiving `(bytes_processed, total)`.
+///
+/// Returns: The decoded bytes, or the first `DecodeError` found.
+pub fn decode_with_progress<F: FnMut(usize, usize)>(
+    data: &str,
+    mut progress: F,
+) -> Result<Vec<u8>, DecodeError> {
+    let config = Base64Config::STANDARD;
+    let input = data.as_bytes();
+    let mut state = DecodeState::new();
+    let mut output = Vec::with_capacity(input.len() / 4 * 3 + 3);
+    let mut processed = 0;
+    // Set once the byte at a time decoder holds state of its own.
+    let mut bytewise = false;
+
+    for chunk in input.chunks(PROGRESS_INTERVAL) {
+        let last = processed + chunk.len() == input.len();
+        if bytewise || last || !decode_whole_groups(chunk, &mut output) {
+            bytewise = true;
+            for (offset, &byte) in chunk.iter().enumerate() {
+                state
+                    .push(&config, processed + offset, byte, &mut output)
+                    .map_err(|err| err.report_str(data))?;
+            }
+        }
+        processed += chunk.len();
+        progress(processed, input.len());
+    }
+    if input.is_empty() {
+        progress(0, 0);
+    }
+
+    state
+        .finish(&config, &mut output)
+        .map_err(|err| err.report_str(data))?;
+    Ok(output)
+}
+
+/// Decodes `chunk` into `out` if it is only whole groups of alphabet
+/// characters, which `PROGRESS_INTERVAL` being a multiple of 4 makes the
+/// common case. Returns `false`, leaving `out` as it was, otherwise.
+fn decode_whole_groups(chunk: &[u8], out: &mut Vec<u8>) -> bool {
+    // Padding is only accepted at the end of `chunk`, which is not the
+    // end of the input.
+    if chunk.last() == Some(&(PADDING as u8)) {
+        return false;
+    }
+    let len = out.len();
+    let decoded = for_each_group(chunk, |bytes| out.extend_from_slice(bytes)).is_ok();
+    if !decoded {
+        out.truncate(len);
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_encode};
+
+    #[test]
+    fn encode_reports_coarse_progress_up_to_total() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let mut calls = Vec::new();
+        let encoded = encode_with_progress(&data, |done, total| calls.push((done, total)));
+
+        assert_eq!(encoded, base64_encode(&data));
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0], (PROGRESS_INTERVAL, data.len()));
+        assert_eq!(calls.last(), Some(&(data.len(), data.len())));
+    }
+
+    #[test]
+    fn encode_empty_still_reports_completion() {
+        let mut calls = Vec::new();
+        assert_eq!(encode_with_progress(b"", |d, t| calls.push((d, t))), "");
+        assert_eq!(calls, [(0, 0)]);
+    }
+
+    #[test]
+    fn decode_reports_coarse_progress_up_to_total() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let encoded = base64_encode(&data);
+        let mut calls = Vec::new();
+        let decoded = decode_with_progress(&encoded, |done, total| calls.push((done, total)));
+
+        assert_eq!(decoded.unwrap(), data);
+        assert_eq!(calls.len(), encoded.len().div_ceil(PROGRESS_INTERVAL));
+        assert_eq!(calls[0], (PROGRESS_INTERVAL, encoded.len()));
+        assert_eq!(calls.last(), Some(&(encoded.len(), encoded.len())));
+    }
+
+    #[test]
+    fn decode_progress_stops_at_the_error() {
+        let mut encoded = base64_encode(&[0x42; 100_000]);
+        encoded.replace_range(PROGRESS_INTERVAL + 10..PROGRESS_INTERVAL + 11, "$");
+        let mut calls = Vec::new();
+        let err = decode_with_progress(&encoded, |d, t| calls.push((d, t))).unwrap_err();
+
+        assert_eq!(err, base64_decode(&encoded).unwrap_err());
+        assert_eq!(err.index(), Some(PROGRESS_INTERVAL + 10));
+        assert_eq!(calls, [(PROGRESS_INTERVAL, encoded.len())]);
+        assert_eq!(decode_with_progress("", |_, _| {}), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn decode_progress_matches_errors_in_any_interval() {
+        let encoded = base64_encode(&(0..150_000u32).map(|i| i as u8).collect::<Vec<_>>());
+        let late = PROGRESS_INTERVAL * 2 + 7;
+        for (at, bad) in [
+            (3, "$"),
+            (PROGRESS_INTERVAL - 1, "="),
+            (PROGRESS_INTERVAL - 2, "="),
+            (PROGRESS_INTERVAL, "="),
+            (late, "!"),
+        ] {
+            let mut corrupt = encoded.clone();
+            corrupt.replace_range(at..at + 1, bad);
+            assert_eq!(
+                decode_with_progress(&corrupt, |_, _| {}),
+                base64_decode(&corrupt),
+                "{} at {}",
+                bad,
+                at
+            );
+        }
+        assert_eq!(
+            decode_with_progress(&encoded[..PROGRESS_INTERVAL], |_, _| {}),
+            base64_decode(&encoded[..PROGRESS_INTERVAL])
+        );
+    }
+}
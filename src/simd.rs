@@ -0,0 +1,386 @@
+//! Vectorized decoding for CPUs that support it
+//!
+//! x86_64 has an AVX2 path for blocks of 32 characters and an SSSE3 path
+//! for blocks of 16, picked at runtime, and little-endian aarch64 has a
+//! NEON path for blocks of 16. On every other target, and for any block
+//! holding a byte outside the alphabet, the group at a time decoder of
+//! `decode_strict_aligned` takes over.
+
+use crate::fast::{check_structure, decode_groups};
+use crate::DecodeError;
+
+/// Decodes clean, padded Base64 like `decode_strict_aligned`, translating
+/// 32 or 16 characters into 24 or 12 bytes at a time where the CPU allows
+/// it.
+///
+/// The result, including the error and its index for invalid input, is
+/// always identical to `decode_strict_aligned`: as soon as a block of
+/// characters holds anything but alphabet characters, the rest of the
+/// input is handed to the scalar decoder, which reports the exact byte.
+///
+/// Parameters:
+/// - `data`: A padded Base64 encoded string.
+///
+/// Returns: The decoded bytes, or the `DecodeError` documented on
+/// `decode_strict_aligned`.
+pub fn decode_simd(data: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_vectorized(data.as_bytes()).map_err(|err| err.report_str(data))
+}
+
+/// Names the path `decode_simd` takes on this CPU: `"avx2"` (which also
+/// uses SSSE3 for a trailing block of 16), `"ssse3"`, `"neon"` or
+/// `"scalar"`.
+///
+/// Example:
+/// ```
+/// use base64::simd_path;
+///
+/// assert!(["avx2", "ssse3", "neon", "scalar"].contains(&simd_path()));
+/// ```
+pub fn simd_path() -> &'static str {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("ssse3") {
+            return "avx2";
+        }
+        if std::is_x86_feature_detected!("ssse3") {
+            return "ssse3";
+        }
+    }
+    #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        return "neon";
+    }
+    "scalar"
+}
+
+fn decode_vectorized(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    if !data.len().is_multiple_of(4) {
+        return Err(DecodeError::InvalidLength { len: data.len() });
+    }
+    check_structure(data)?;
+
+    let mut output = Vec::with_capacity(data.len() / 4 * 3);
+    // The last group may be padded, so it is always left to the scalar
+    // decoder.
+    let done = decode_blocks(&data[..data.len().saturating_sub(4)], &mut output);
+    let rest = decode_groups(&data[done..]).map_err(|err| err.shifted(done))?;
+    output.extend_from_slice(&rest);
+    Ok(output)
+}
+
+/// Decodes leading blocks of alphabet characters of `data` into `out` and
+/// returns how many input bytes were consumed.
+///
+/// AVX2 takes blocks of 32 characters; SSSE3 then takes what is left in
+/// blocks of 16, which also covers a clean half of the block AVX2 stopped
+/// at.
+#[cfg(target_arch = "x86_64")]
+fn decode_blocks(data: &[u8], out: &mut Vec<u8>) -> usize {
+    let mut done = 0;
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        done = unsafe { avx2::decode_blocks(data, out) };
+    }
+    if std::is_x86_feature_detected!("ssse3") {
+        // SAFETY: the CPU supports SSSE3.
+        done += unsafe { ssse3::decode_blocks(&data[done..], out) };
+    }
+    done
+}
+
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+fn decode_blocks(data: &[u8], out: &mut Vec<u8>) -> usize {
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        // SAFETY: the CPU supports NEON.
+        unsafe { neon::decode_blocks(data, out) }
+    } else {
+        0
+    }
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", target_endian = "little")
+)))]
+fn decode_blocks(_data: &[u8], _out: &mut Vec<u8>) -> usize {
+    0
+}
+
+/// Flags for each low nibble of a character; see `ssse3::decode_blocks`.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const LUT_LO: [u8; 16] = [
+    0x15, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x13, 0x1a, 0x1b, 0x1b, 0x1b, 0x1a,
+];
+
+/// Flags for each high nibble of a character.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const LUT_HI: [u8; 16] = [
+    0x10, 0x10, 0x01, 0x02, 0x04, 0x08, 0x04, 0x08, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10,
+];
+
+/// The offset from a character to its sextet, by high nibble, with `/`
+/// moved to slot 1.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const LUT_ROLL: [i8; 16] = [0, 16, 19, 4, -65, -65, -71, -71, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// Moves the 3 bytes of each 24 bit group to the front, in order.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const PACK: [i8; 16] = [2, 1, 0, 6, 5, 4, 10, 9, 8, 14, 13, 12, -1, -1, -1, -1];
+
+#[cfg(target_arch = "x86_64")]
+mod ssse3 {
+    use super::{LUT_HI, LUT_LO, LUT_ROLL, PACK};
+    use std::arch::x86_64::*;
+
+    /// The SSSE3 version of `decode_blocks`.
+    ///
+    /// Characters are classified by their nibbles: each low nibble and
+    /// each high nibble maps to a set of flags, and a character is valid
+    /// exactly when its two sets are disjoint. The high nibble, corrected
+    /// for `/`, then selects the offset that turns the character into its
+    /// sextet. Two multiply-adds merge the sextets into 24 bit groups,
+    /// which a final shuffle packs into 12 bytes.
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn decode_blocks(data: &[u8], out: &mut Vec<u8>) -> usize {
+        let lut_lo = _mm_loadu_si128(LUT_LO.as_ptr().cast());
+        let lut_hi = _mm_loadu_si128(LUT_HI.as_ptr().cast());
+        let lut_roll = _mm_loadu_si128(LUT_ROLL.as_ptr().cast());
+        let pack = _mm_loadu_si128(PACK.as_ptr().cast());
+        let nibble = _mm_set1_epi8(0x0f);
+        let slash = _mm_set1_epi8(b'/' as i8);
+
+        let mut done = 0;
+        for block in data.chunks_exact(16) {
+            let input = _mm_loadu_si128(block.as_ptr().cast());
+            let hi_nibbles = _mm_and_si128(_mm_srli_epi32(input, 4), nibble);
+            let lo_nibbles = _mm_and_si128(input, nibble);
+            let flags = _mm_and_si128(
+                _mm_shuffle_epi8(lut_lo, lo_nibbles),
+                _mm_shuffle_epi8(lut_hi, hi_nibbles),
+            );
+            if _mm_movemask_epi8(_mm_cmpgt_epi8(flags, _mm_setzero_si128())) != 0 {
+                break;
+            }
+
+            let roll = _mm_shuffle_epi8(
+                lut_roll,
+                _mm_add_epi8(_mm_cmpeq_epi8(input, slash), hi_nibbles),
+            );
+            let sextets = _mm_add_epi8(input, roll);
+            let pairs = _mm_maddubs_epi16(sextets, _mm_set1_epi32(0x0140_0140));
+            let groups = _mm_madd_epi16(pairs, _mm_set1_epi32(0x0001_1000));
+
+            let mut bytes = [0u8; 16];
+            _mm_storeu_si128(bytes.as_mut_ptr().cast(), _mm_shuffle_epi8(groups, pack));
+            out.extend_from_slice(&bytes[..12]);
+            done += 16;
+        }
+        done
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use super::{LUT_HI, LUT_LO, LUT_ROLL, PACK};
+    use std::arch::x86_64::*;
+
+    /// The AVX2 version of `decode_blocks`, taking 32 characters at a
+    /// time.
+    ///
+    /// It is the SSSE3 algorithm on each 128 bit lane: AVX2 shuffles stay
+    /// within a lane, so every table is repeated in both, and each lane
+    /// yields its own 12 bytes.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn decode_blocks(data: &[u8], out: &mut Vec<u8>) -> usize {
+        let both_lanes =
+            |table: *const u8| _mm256_broadcastsi128_si256(_mm_loadu_si128(table.cast()));
+        let lut_lo = both_lanes(LUT_LO.as_ptr());
+        let lut_hi = both_lanes(LUT_HI.as_ptr());
+        let lut_roll = both_lanes(LUT_ROLL.as_ptr().cast());
+        let pack = both_lanes(PACK.as_ptr().cast());
+        let nibble = _mm256_set1_epi8(0x0f);
+        let slash = _mm256_set1_epi8(b'/' as i8);
+
+        let mut done = 0;
+        for block in data.chunks_exact(32) {
+            let input = _mm256_loadu_si256(block.as_ptr().cast());
+            let hi_nibbles = _mm256_and_si256(_mm256_srli_epi32(input, 4), nibble);
+            let lo_nibbles = _mm256_and_si256(input, nibble);
+            let flags = _mm256_and_si256(
+                _mm256_shuffle_epi8(lut_lo, lo_nibbles),
+                _mm256_shuffle_epi8(lut_hi, hi_nibbles),
+            );
+            if _mm256_movemask_epi8(_mm256_cmpgt_epi8(flags, _mm256_setzero_si256())) != 0 {
+                break;
+            }
+
+            let roll = _mm256_shuffle_epi8(
+                lut_roll,
+                _mm256_add_epi8(_mm256_cmpeq_epi8(input, slash), hi_nibbles),
+            );
+            let sextets = _mm256_add_epi8(input, roll);
+            let pairs = _mm256_maddubs_epi16(sextets, _mm256_set1_epi32(0x0140_0140));
+            let groups = _mm256_madd_epi16(pairs, _mm256_set1_epi32(0x0001_1000));
+
+            let mut bytes = [0u8; 32];
+            _mm256_storeu_si256(bytes.as_mut_ptr().cast(), _mm256_shuffle_epi8(groups, pack));
+            out.extend_from_slice(&bytes[..12]);
+            out.extend_from_slice(&bytes[16..28]);
+            done += 32;
+        }
+        done
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+mod neon {
+    use super::{LUT_HI, LUT_LO, LUT_ROLL, PACK};
+    use std::arch::aarch64::*;
+
+    /// The NEON version of `decode_blocks`.
+    ///
+    /// Classification and the sextet offsets are those of the SSSE3
+    /// version, with table lookups in place of byte shuffles. NEON has no
+    /// multiply-add across neighbouring lanes, so the sextets are merged
+    /// into 24 bit groups with shifts on 16 and then 32 bit lanes, which
+    /// leaves the same layout for the final shuffle.
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn decode_blocks(data: &[u8], out: &mut Vec<u8>) -> usize {
+        let lut_lo = vld1q_u8(LUT_LO.as_ptr());
+        let lut_hi = vld1q_u8(LUT_HI.as_ptr());
+        let lut_roll = vld1q_u8(LUT_ROLL.as_ptr().cast());
+        let pack = vld1q_u8(PACK.as_ptr().cast());
+        let nibble = vdupq_n_u8(0x0f);
+        let slash = vdupq_n_u8(b'/');
+
+        let mut done = 0;
+        for block in data.chunks_exact(16) {
+            let input = vld1q_u8(block.as_ptr());
+            let hi_nibbles = vshrq_n_u8(input, 4);
+            let lo_nibbles = vandq_u8(input, nibble);
+            let flags = vandq_u8(
+                vqtbl1q_u8(lut_lo, lo_nibbles),
+                vqtbl1q_u8(lut_hi, hi_nibbles),
+            );
+            if vmaxvq_u8(flags) != 0 {
+                break;
+            }
+
+            let roll = vqtbl1q_u8(lut_roll, vaddq_u8(vceqq_u8(input, slash), hi_nibbles));
+            let sextets = vreinterpretq_u16_u8(vaddq_u8(input, roll));
+            // Each 16 bit lane holds sextets `a` and `b`, low byte first.
+            let pairs = vorrq_u16(
+                vshlq_n_u16(vandq_u16(sextets, vdupq_n_u16(0xff)), 6),
+                vshrq_n_u16(sextets, 8),
+            );
+            let pairs = vreinterpretq_u32_u16(pairs);
+            let groups = vorrq_u32(
+                vshlq_n_u32(vandq_u32(pairs, vdupq_n_u32(0xffff)), 12),
+                vshrq_n_u32(pairs, 16),
+            );
+
+            let mut bytes = [0u8; 16];
+            vst1q_u8(
+                bytes.as_mut_ptr(),
+                vqtbl1q_u8(vreinterpretq_u8_u32(groups), pack),
+            );
+            out.extend_from_slice(&bytes[..12]);
+            done += 16;
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_encode, decode_strict_aligned};
+
+    /// A small xorshift generator, so the inputs are the same every run.
+    fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simd_matches_scalar_on_random_input() {
+        for len in 0..300 {
+            let data = random_bytes(len as u64, len);
+            let encoded = base64_encode(&data);
+            assert_eq!(decode_simd(&encoded).unwrap(), data);
+            assert_eq!(decode_simd(&encoded), decode_strict_aligned(&encoded));
+        }
+    }
+
+    #[test]
+    fn simd_reports_the_same_errors_as_scalar() {
+        // ASCII only, so the corrupt inputs keep their length.
+        let encoded = base64_encode(&random_bytes(7, 600));
+        for (i, byte) in random_bytes(11, 64).into_iter().enumerate() {
+            let mut corrupt = encoded.clone().into_bytes();
+            corrupt[i * 12 % encoded.len()] = byte & 0x7f;
+            let corrupt = String::from_utf8(corrupt).unwrap();
+            assert_eq!(decode_simd(&corrupt), decode_strict_aligned(&corrupt));
+        }
+        for byte in 0..0x80u8 {
+            for at in [5, 20, 40] {
+                let mut block = *b"QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVphYmNkZWZnaGlq";
+                block[at] = byte;
+                let block = String::from_utf8(block.to_vec()).unwrap();
+                assert_eq!(decode_simd(&block), decode_strict_aligned(&block));
+            }
+        }
+    }
+
+    #[test]
+    fn simd_reports_non_ascii_characters_like_scalar() {
+        let encoded = base64_encode(&random_bytes(13, 90));
+        for ch in ['\u{e9}', '\u{2026}', '\u{1f600}'] {
+            for at in [0, 17, 40, 100] {
+                // Replace as many characters as `ch` has bytes.
+                let mut corrupt = encoded.clone();
+                corrupt.replace_range(at..at + ch.len_utf8(), ch.encode_utf8(&mut [0; 4]));
+                assert_eq!(corrupt.len(), encoded.len());
+                assert!(decode_simd(&corrupt).is_err());
+                assert_eq!(decode_simd(&corrupt), decode_strict_aligned(&corrupt));
+            }
+        }
+    }
+
+    #[test]
+    fn vector_path_stops_at_the_first_bad_block() {
+        let mut out = Vec::new();
+        let done = decode_blocks(b"QUJDREVGR0hJSktMTU5PUFFSU1RV$1hZ", &mut out);
+        if simd_path() != "scalar" {
+            assert_eq!(done, 16);
+            assert_eq!(out, b"ABCDEFGHIJKL");
+            return;
+        }
+        assert_eq!((done, out.len()), (0, 0));
+        assert_eq!(simd_path(), "scalar");
+    }
+
+    #[test]
+    fn wide_blocks_then_narrow_blocks_are_decoded() {
+        // Three clean blocks of 32, then a clean 16 and a bad 16.
+        let mut input = base64_encode(&random_bytes(3, 72)).into_bytes();
+        input.extend_from_slice(b"QUJDREVGR0hJSktMTU5PUFFSU1RV$1hZ");
+        let mut out = Vec::new();
+        let done = decode_blocks(&input, &mut out);
+        if simd_path() != "scalar" {
+            assert_eq!(done, 112);
+            assert_eq!(&out[..72], &random_bytes(3, 72)[..]);
+            assert_eq!(&out[72..], b"ABCDEFGHIJKL");
+            return;
+        }
+        assert_eq!((done, out.len()), (0, 0));
+    }
+}
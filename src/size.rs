@@ -0,0 +1,126 @@
+//! Sizes of Base64 encoded and decoded data, for budgeting storage and
+//! bandwidth
+
+use crate::PADDING;
+
+/// Returns the length of the padded encoding of `input_len` bytes.
+pub const fn encoded_len(input_len: usize) -> usize {
+    input_len.div_ceil(3) * 4
+}
+
+/// Returns the number of data characters encoding `input_len` bytes
+/// produces, without padding, e.g. to check that a token fits in a URL.
+pub const fn unpadded_encoded_len(input_len: usize) -> usize {
+    // ceil(input_len * 8 / 6), split so that `input_len * 8` cannot
+    // overflow.
+    input_len / 3 * 4 + (input_len % 3 * 8).div_ceil(6)
+}
+
+/// Returns the length of `encode_wrapped` output for `input_len` bytes.
+///
+/// That is the padded encoding plus one `\n` between each pair of lines.
+/// A `line_width` of 0 means no wrapping.
+pub const fn encoded_len_wrapped(input_len: usize, line_width: usize) -> usize {
+    let len = encoded_len(input_len);
+    if line_width == 0 || len == 0 {
+        return len;
+    }
+    len + (len - 1) / line_width
+}
+
+/// Returns how many encoded bytes each input byte costs in the long run.
+///
+/// This is always 4/3; padding and line breaks add a little on top for
+/// any particular length, see `overhead_bytes`.
+pub fn overhead_ratio() -> f64 {
+    4.0 / 3.0
+}
+
+/// Returns how many bytes encoding `input_len` bytes adds, padding and
+/// line breaks included.
+///
+/// Parameters:
+/// - `input_len`: The number of bytes to be encoded.
+/// - `line_width`: The line width passed to `encode_wrapped`, or 0 for
+///   unwrapped output.
+///
+/// Returns: The encoded length minus `input_len`.
+pub const fn overhead_bytes(input_len: usize, line_width: usize) -> usize {
+    encoded_len_wrapped(input_len, line_width) - input_len
+}
+
+/// Returns the number of bytes padded Base64 `data` decodes to, looking
+/// only at its length and last 2 characters.
+///
+/// This takes constant time however long `data` is, but assumes it is
+/// well-formed: no whitespace or line breaks, and a length that is a
+/// multiple of 4. Other input gives a meaningless result; use
+/// `const_decoded_len` to have the length checked.
+///
+/// Example:
+/// ```
+/// use base64::decoded_len_fast;
+///
+/// assert_eq!(decoded_len_fast("TWFueSBoYW5kcw=="), 10);
+/// ```
+pub const fn decoded_len_fast(data: &str) -> usize {
+    let data = data.as_bytes();
+    let mut padding = 0;
+    while padding < 2 && padding < data.len() && data[data.len() - 1 - padding] == PADDING as u8 {
+        padding += 1;
+    }
+    (data.len() / 4 * 3).saturating_sub(padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base64_decode, base64_encode, base64_encode_nopad, const_decoded_len, encode_wrapped,
+    };
+
+    #[test]
+    fn overhead_matches_the_actual_output() {
+        let data = [0x5a; 200];
+        for len in 0..data.len() {
+            for line_width in [0, 1, 4, 63, 76] {
+                let encoded = encode_wrapped(&data[..len], line_width);
+                assert_eq!(encoded.len(), encoded_len_wrapped(len, line_width));
+                assert_eq!(len + overhead_bytes(len, line_width), encoded.len());
+            }
+        }
+        assert_eq!(overhead_bytes(3, 0), 1);
+        assert_eq!(overhead_bytes(1, 0), 3);
+        assert!((overhead_ratio() * 3.0 - 4.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn unpadded_len_matches_the_no_pad_encoder() {
+        let data = [0xa5; 10];
+        for len in 0..=data.len() {
+            assert_eq!(
+                unpadded_encoded_len(len),
+                base64_encode_nopad(&data[..len]).len()
+            );
+        }
+        assert_eq!(unpadded_encoded_len(usize::MAX / 4 * 3), usize::MAX / 4 * 4);
+    }
+
+    #[test]
+    fn fast_decoded_len_matches_a_full_decode() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 37 % 256) as u8).collect();
+        for len in 0..data.len() {
+            let encoded = base64_encode(&data[..len]);
+            assert_eq!(decoded_len_fast(&encoded), len);
+            assert_eq!(
+                decoded_len_fast(&encoded),
+                base64_decode(&encoded).unwrap().len()
+            );
+            assert_eq!(
+                Some(decoded_len_fast(&encoded)),
+                const_decoded_len(encoded.as_bytes())
+            );
+        }
+        assert_eq!(decoded_len_fast("=="), 0);
+    }
+}
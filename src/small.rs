@@ -0,0 +1,143 @@
+//! Decoding small tokens without a heap allocation
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::decoder::exact_decoded_len;
+use crate::fast::for_each_group;
+use crate::{base64_decode, DecodeError, PADDING};
+
+/// Decoded outputs of up to this many bytes are stored inline by
+/// `decode_small`.
+pub const INLINE_CAPACITY: usize = 32;
+
+/// Decoded bytes that are kept inline when there are few enough of them,
+/// and on the heap otherwise.
+///
+/// It dereferences to `[u8]` either way, so callers need not care which.
+#[derive(Clone)]
+pub struct SmallBytes(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        bytes: [u8; INLINE_CAPACITY],
+        len: u8,
+    },
+    Heap(Vec<u8>),
+}
+
+impl SmallBytes {
+    /// Returns whether the bytes are stored inline rather than on the heap.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Moves the bytes into a `Vec`, which allocates if they were inline.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.0 {
+            Repr::Inline { bytes, len } => bytes[..len as usize].to_vec(),
+            Repr::Heap(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for SmallBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.0 {
+            Repr::Inline { bytes, len } => &bytes[..*len as usize],
+            Repr::Heap(bytes) => bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for SmallBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl PartialEq for SmallBytes {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for SmallBytes {}
+
+impl fmt::Debug for SmallBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SmallBytes").field(&&**self).finish()
+    }
+}
+
+/// Decodes `data` like `base64_decode`, but without a heap allocation if
+/// it decodes to at most `INLINE_CAPACITY` bytes, for servers that decode
+/// many tiny tokens.
+///
+/// Example:
+/// ```
+/// use base64::decode_small;
+///
+/// let decoded = decode_small("TWFueQ==").unwrap();
+/// assert!(decoded.is_inline());
+/// assert_eq!(&*decoded, b"Many");
+/// ```
+///
+/// Returns: The decoded bytes, inline or on the heap, or the first
+/// `DecodeError` found.
+pub fn decode_small(data: &str) -> Result<SmallBytes, DecodeError> {
+    let input = data.as_bytes();
+    // The exact length for valid input; anything else is caught below, by
+    // running out of room or by the group decoder.
+    if exact_decoded_len(input, PADDING as u8) <= INLINE_CAPACITY {
+        let mut bytes = [0; INLINE_CAPACITY];
+        let mut len = 0;
+        let mut overflow = false;
+        let decoded = for_each_group(input, |group| match bytes.get_mut(len..len + group.len()) {
+            Some(slot) => {
+                slot.copy_from_slice(group);
+                len += group.len();
+            }
+            None => overflow = true,
+        });
+        // Errors come from `base64_decode` below, so that they are the
+        // same as there and reported only once.
+        if decoded.is_ok() && !overflow {
+            return Ok(SmallBytes(Repr::Inline {
+                bytes,
+                len: len as u8,
+            }));
+        }
+    }
+    base64_decode(data).map(|bytes| SmallBytes(Repr::Heap(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_encode;
+
+    #[test]
+    fn small_outputs_stay_inline() {
+        let data: Vec<u8> = (0..100u8).collect();
+        for len in 0..data.len() {
+            let decoded = decode_small(&base64_encode(&data[..len])).unwrap();
+            assert_eq!(&*decoded, &data[..len]);
+            assert_eq!(decoded.is_inline(), len <= INLINE_CAPACITY);
+            assert_eq!(decoded.into_vec(), &data[..len]);
+        }
+    }
+
+    #[test]
+    fn small_errors_match_base64_decode() {
+        for input in ["TWF", "TW$u", "=TWFu", "TQ===", "TWFu$", &"A".repeat(45)] {
+            assert_eq!(
+                decode_small(input).map(SmallBytes::into_vec),
+                base64_decode(input)
+            );
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! Streaming encoding and decoding over `std::io`
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::decoder::DecodeState;
+use crate::{Base64Config, Base64Encoder, DecodeError};
+
+/// The internal buffer size used by `new`, in bytes.
+///
+/// 8KB is large enough to amortize the cost of calls into the wrapped
+/// reader or writer on big files, while staying small enough to not matter
+/// on most systems. Use `with_capacity` to tune it either way.
+pub const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A writer that Base64 encodes everything written to it before passing
+/// it on to the wrapped writer.
+///
+/// Encoded output is collected in an internal buffer of roughly the
+/// chosen capacity and written to the inner writer whenever it fills up.
+/// A larger buffer means fewer, bigger writes; a smaller one bounds
+/// memory use. Call `finish` once all data is written to emit the last
+/// group with its padding.
+///
+/// Example:
+/// ```
+/// use std::io::Write;
+/// use base64::EncoderWriter;
+///
+/// let mut writer = EncoderWriter::new(Vec::new());
+/// writer.write_all(b"Many hands").unwrap();
+/// assert_eq!(writer.finish().unwrap(), b"TWFueSBoYW5kcw==");
+/// ```
+#[derive(Debug)]
+pub struct EncoderWriter<W: Write> {
+    inner: W,
+    encoder: Base64Encoder,
+    buffer: String,
+    capacity: usize,
+}
+
+impl<W: Write> EncoderWriter<W> {
+    /// Wraps `inner` with a buffer of `DEFAULT_BUFFER_SIZE` bytes.
+    pub fn new(inner: W) -> Self {
+        EncoderWriter::with_capacity(DEFAULT_BUFFER_SIZE, inner)
+    }
+
+    /// Wraps `inner` with a buffer of about `capacity` encoded bytes.
+    ///
+    /// Capacities below 4 (one encoded group) are rounded up to 4.
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        let capacity = capacity.max(4);
+        EncoderWriter {
+            inner,
+            encoder: Base64Encoder::new(),
+            buffer: String::with_capacity(capacity + 4),
+            capacity,
+        }
+    }
+
+    /// Writes out all buffered output, pads the final group and returns
+    /// the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        std::mem::take(&mut self.encoder).finalize_into(&mut self.buffer);
+        self.write_buffer()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.inner.write_all(self.buffer.as_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let step = self.capacity / 4 * 3;
+        let mut written = 0;
+        for piece in data.chunks(step) {
+            if self.buffer.len() >= self.capacity {
+                if let Err(err) = self.write_buffer() {
+                    return if written > 0 { Ok(written) } else { Err(err) };
+                }
+            }
+            self.encoder.update_into(piece, &mut self.buffer);
+            written += piece.len();
+        }
+        Ok(written)
+    }
+
+    /// Writes out all complete groups. Up to 2 trailing input bytes stay
+    /// buffered, since they cannot be encoded before more input or
+    /// `finish` arrives.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer()?;
+        self.inner.flush()
+    }
+}
+
+/// A writer that passes everything written to it on to one writer
+/// unchanged and to another Base64 encoded, e.g. to keep an encoded copy
+/// of some output for logging.
+///
+/// The encoded side is an [`EncoderWriter`], so call `finish` once all
+/// data is written to emit its last group with padding.
+///
+/// Example:
+/// ```
+/// use std::io::Write;
+/// use base64::TeeEncoder;
+///
+/// let mut tee = TeeEncoder::new(Vec::new(), Vec::new());
+/// tee.write_all(b"Many hands").unwrap();
+/// let (raw, encoded) = tee.finish().unwrap();
+/// assert_eq!(raw, b"Many hands");
+/// assert_eq!(encoded, b"TWFueSBoYW5kcw==");
+/// ```
+#[derive(Debug)]
+pub struct TeeEncoder<W1: Write, W2: Write> {
+    raw: W1,
+    encoded: EncoderWriter<W2>,
+}
+
+impl<W1: Write, W2: Write> TeeEncoder<W1, W2> {
+    /// Writes the raw bytes to `raw` and their encoding to `encoded`.
+    pub fn new(raw: W1, encoded: W2) -> Self {
+        TeeEncoder {
+            raw,
+            encoded: EncoderWriter::new(encoded),
+        }
+    }
+
+    /// Pads the final encoded group, flushes both writers and returns
+    /// them.
+    pub fn finish(mut self) -> io::Result<(W1, W2)> {
+        self.raw.flush()?;
+        let encoded = self.encoded.finish()?;
+        Ok((self.raw, encoded))
+    }
+}
+
+impl<W1: Write, W2: Write> Write for TeeEncoder<W1, W2> {
+    /// Writes to the raw side first, then encodes exactly the bytes it
+    /// accepted, so both sides always hold the same data.
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let written = self.raw.write(data)?;
+        self.encoded.write_all(&data[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.raw.flush()?;
+        self.encoded.flush()
+    }
+}
+
+/// A reader that decodes the Base64 read from the wrapped reader.
+///
+/// Encoded input is read from the inner reader into an internal buffer of
+/// the chosen capacity. Decoding errors are returned as
+/// `io::ErrorKind::InvalidData` wrapping the `DecodeError`, whose index
+/// counts from the start of the stream.
+///
+/// Example:
+/// ```
+/// use std::io::Read;
+/// use base64::DecoderReader;
+///
+/// let mut decoded = Vec::new();
+/// DecoderReader::new(&b"TWFueSBoYW5kcw=="[..]).read_to_end(&mut decoded).unwrap();
+/// assert_eq!(decoded, b"Many hands");
+/// ```
+#[derive(Debug)]
+pub struct DecoderReader<R: Read> {
+    inner: R,
+    config: Base64Config,
+    state: Option<DecodeState>,
+    input: Vec<u8>,
+    decoded: Vec<u8>,
+    position: usize,
+    offset: usize,
+}
+
+/// Rounds the capacity of a `DecoderReader` up to a multiple of 4, at
+/// least 4. Capacities too large to round up are rounded down instead,
+/// rather than overflowing.
+fn read_capacity(capacity: usize) -> usize {
+    capacity.clamp(4, usize::MAX & !3).next_multiple_of(4)
+}
+
+impl<R: Read> DecoderReader<R> {
+    /// Wraps `inner` with a buffer of `DEFAULT_BUFFER_SIZE` bytes.
+    pub fn new(inner: R) -> Self {
+        DecoderReader::with_capacity(DEFAULT_BUFFER_SIZE, inner)
+    }
+
+    /// Wraps `inner`, reading at most `capacity` encoded bytes at a time.
+    ///
+    /// Larger buffers suit fast sources such as files, smaller ones keep
+    /// latency down on slow sockets. The capacity is rounded up to a
+    /// multiple of 4, at least 4, so every full read holds whole groups.
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        let capacity = read_capacity(capacity);
+        DecoderReader {
+            inner,
+            config: Base64Config::new(),
+            state: Some(DecodeState::new()),
+            input: vec![0; capacity],
+            decoded: Vec::with_capacity(capacity / 4 * 3 + 3),
+            position: 0,
+            offset: 0,
+        }
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and decodes the next buffer of input.
+    fn fill_decoded(&mut self) -> io::Result<()> {
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+        let read = loop {
+            match self.inner.read(&mut self.input) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+
+        if read == 0 {
+            let state = self.state.take().expect("state checked above");
+            return state
+                .finish(&self.config, &mut self.decoded)
+                .map_err(invalid_data);
+        }
+        for (i, &byte) in self.input[..read].iter().enumerate() {
+            state
+                .push(&self.config, self.offset + i, byte, &mut self.decoded)
+                .map_err(invalid_data)?;
+        }
+        self.offset += read;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecoderReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            if self.state.is_none() {
+                return Ok(0);
+            }
+            self.decoded.clear();
+            self.position = 0;
+            self.fill_decoded()?;
+        }
+
+        let available = &self.decoded[self.position..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+pub(crate) fn invalid_data(err: DecodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// The error of `decode_byte_results`: either reading the input failed,
+/// or what was read is not valid Base64.
+#[derive(Debug)]
+pub enum DecodeIoError {
+    /// The input iterator yielded an I/O error.
+    Io(io::Error),
+    /// The input could not be decoded.
+    Decode(DecodeError),
+}
+
+impl fmt::Display for DecodeIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeIoError::Io(err) => write!(f, "Failed to read base64: {}", err),
+            DecodeIoError::Decode(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for DecodeIoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeIoError::Io(err) => Some(err),
+            DecodeIoError::Decode(err) => Some(err),
+        }
+    }
+}
+
+/// Encodes everything `reader` yields into `writer` with `config`, in
+/// chunks of `DEFAULT_BUFFER_SIZE`, without holding the whole input or
+/// output in memory.
+///
+/// The alphabet and padding of `config` are used; its decoding options
+/// do not matter here. `writer` is flushed once the last group is out.
+///
+/// Parameters:
+/// - `reader`: The data to be encoded.
+/// - `writer`: Where the Base64 is written.
+/// - `config`: The encoding options.
+///
+/// Returns: The number of input bytes encoded, or the first I/O error
+/// from either side.
+pub fn encode_stream<R: Read, W: Write>(
+    mut reader: R,
+    writer: W,
+    config: &Base64Config,
+) -> io::Result<u64> {
+    let mut encoder = EncoderWriter::new(writer);
+    encoder.encoder = Base64Encoder::with_config(config.clone());
+    let read = io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(read)
+}
+
+/// Decodes bytes as they come out of a fallible iterator, such as
+/// `Read::bytes()`, without buffering the input first.
+///
+/// Example:
+/// ```
+/// use std::io::{Cursor, Read};
+/// use base64::decode_byte_results;
+///
+/// let decoded = decode_byte_results(Cursor::new("TWFu").bytes()).unwrap();
+/// assert_eq!(decoded, b"Man");
+/// ```
+///
+/// Parameters:
+/// - `iter`: The encoded input, one byte at a time.
+///
+/// Returns: The decoded bytes, or the first I/O or `DecodeError` met.
+/// Decode error indices count the bytes yielded before the bad one.
+pub fn decode_byte_results<I: Iterator<Item = io::Result<u8>>>(
+    iter: I,
+) -> Result<Vec<u8>, DecodeIoError> {
+    let config = Base64Config::new();
+    let mut state = DecodeState::new();
+    let mut out = Vec::new();
+    for (index, byte) in iter.enumerate() {
+        let byte = byte.map_err(DecodeIoError::Io)?;
+        state
+            .push(&config, index, byte, &mut out)
+            .map_err(DecodeIoError::Decode)?;
+    }
+    state
+        .finish(&config, &mut out)
+        .map_err(DecodeIoError::Decode)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_encode};
+
+    fn sample() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    #[test]
+    fn writer_matches_one_shot_encode_for_any_capacity() {
+        let data = sample();
+        for capacity in [0, 4, 5, 64, DEFAULT_BUFFER_SIZE] {
+            let mut writer = EncoderWriter::with_capacity(capacity, Vec::new());
+            for piece in data.chunks(333) {
+                writer.write_all(piece).unwrap();
+            }
+            let encoded = writer.finish().unwrap();
+            assert_eq!(encoded, base64_encode(&data).as_bytes());
+        }
+    }
+
+    #[test]
+    fn stream_encoding_matches_one_shot() {
+        let data = sample();
+        for config in [Base64Config::STANDARD, Base64Config::URL_SAFE_NO_PAD] {
+            let mut out = Vec::new();
+            let read = encode_stream(io::Cursor::new(&data), &mut out, &config).unwrap();
+            assert_eq!(read, data.len() as u64);
+            assert_eq!(out, config.encode(&data).as_bytes());
+        }
+    }
+
+    #[test]
+    fn writer_flush_does_not_pad() {
+        let mut writer = EncoderWriter::new(Vec::new());
+        writer.write_all(b"Many h").unwrap();
+        writer.write_all(b"an").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.inner, b"TWFueSBo");
+        writer.write_all(b"ds").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.inner, b"TWFueSBoYW5k");
+        assert_eq!(
+            writer.finish().unwrap(),
+            base64_encode(b"Many hands").as_bytes()
+        );
+    }
+
+    #[test]
+    fn tee_writes_raw_and_encoded_copies() {
+        let data = sample();
+        let mut tee = TeeEncoder::new(Vec::new(), Vec::new());
+        for piece in data.chunks(7) {
+            tee.write_all(piece).unwrap();
+        }
+        let (raw, encoded) = tee.finish().unwrap();
+        assert_eq!(raw, data);
+        assert_eq!(encoded, base64_encode(&data).into_bytes());
+    }
+
+    #[test]
+    fn reader_matches_one_shot_decode_for_any_capacity() {
+        let data = sample();
+        let encoded = base64_encode(&data);
+        let rounded_capacities = [
+            (0, 4),
+            (1, 4),
+            (7, 8),
+            (4095, 4096),
+            (DEFAULT_BUFFER_SIZE, DEFAULT_BUFFER_SIZE),
+        ];
+        for (capacity, rounded) in rounded_capacities {
+            let mut reader = DecoderReader::with_capacity(capacity, encoded.as_bytes());
+            assert_eq!(reader.input.len(), rounded);
+            let mut decoded = Vec::new();
+            reader.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+        // Too large to allocate, but the rounding itself must not overflow.
+        assert_eq!(read_capacity(usize::MAX), usize::MAX & !3);
+        assert_eq!(read_capacity(usize::MAX - 4), usize::MAX & !3);
+    }
+
+    #[test]
+    fn reader_reports_decode_errors_with_stream_index() {
+        let mut encoded = base64_encode(&sample());
+        encoded.replace_range(5000..5001, "*");
+        let mut reader = DecoderReader::with_capacity(64, encoded.as_bytes());
+
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.into_inner().unwrap().downcast::<DecodeError>().unwrap();
+        assert_eq!(*inner, DecodeError::invalid_byte(5000, b'*'));
+        assert!(base64_decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn byte_results_decode_off_a_reader() {
+        let data = sample();
+        let encoded = base64_encode(&data);
+        let cursor = io::Cursor::new(encoded.as_bytes());
+        assert_eq!(decode_byte_results(cursor.bytes()).unwrap(), data);
+
+        match decode_byte_results(io::Cursor::new("TW$u").bytes()) {
+            Err(DecodeIoError::Decode(err)) => {
+                assert_eq!(err, DecodeError::invalid_byte(2, b'$'))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn byte_results_stop_at_io_errors() {
+        let failing = b"TWFu"
+            .iter()
+            .map(|&b| Ok(b))
+            .chain([Err(io::Error::other("disk on fire"))])
+            .chain(b"$$$$".iter().map(|&b| Ok(b)));
+        match decode_byte_results(failing) {
+            Err(DecodeIoError::Io(err)) => assert_eq!(err.to_string(), "disk on fire"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Reporting decode failures as `tracing` events
+//!
+//! With the `tracing` feature, every decoder that is given its whole
+//! input at once emits a `tracing::warn!` event for each error it returns
+//! for malformed input (bad characters, padding or length), so a service
+//! can log or count malformed input without wrapping each call.
+//!
+//! Errors about the decoded data or its framing, such as `LengthMismatch`,
+//! `ChecksumMismatch` or `AuthFailed`, are not reported. Neither are those
+//! of decoders that take their input in pieces (`Base64Decoder`,
+//! `Validator`, `decode_chunks`, `DecoderReader`, `transcode_stream`), as
+//! `char`s (`decode_chars`) or at compile time (`const_decode`).
+//! The event only holds the kind and position of the error, as its `kind`
+//! and `index` fields: the input itself is deliberately never logged, as
+//! it is often a token or other secret.
+
+use crate::DecodeError;
+
+/// Emits the warning event for `err`.
+pub(crate) fn decode_failed(err: &DecodeError) {
+    tracing::warn!(
+        kind = kind(err),
+        index = err.index(),
+        "base64 decode failed"
+    );
+}
+
+/// Returns the name of the variant of `err`.
+fn kind(err: &DecodeError) -> &'static str {
+    match err {
+        DecodeError::InvalidByte { .. } => "InvalidByte",
+        DecodeError::NonAsciiByte { .. } => "NonAsciiByte",
+        DecodeError::NonAsciiChar { .. } => "NonAsciiChar",
+        DecodeError::WrongAlphabet { .. } => "WrongAlphabet",
+        DecodeError::InvalidPadding => "InvalidPadding",
+        DecodeError::InvalidLength { .. } => "InvalidLength",
+        DecodeError::MissingPrefix => "MissingPrefix",
+        DecodeError::BadMagic => "BadMagic",
+        DecodeError::LengthMismatch { .. } => "LengthMismatch",
+        DecodeError::IndexOutOfRange { .. } => "IndexOutOfRange",
+        DecodeError::ChecksumMismatch => "ChecksumMismatch",
+        DecodeError::MissingChecksum => "MissingChecksum",
+        DecodeError::AuthFailed => "AuthFailed",
+        DecodeError::NotUtf8 { .. } => "NotUtf8",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{base64_decode, decode_strict_aligned};
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    /// The level, `kind` and `index` of each event, and whether any other
+    /// field was recorded.
+    type Seen = Vec<(Level, String, Option<u64>, bool)>;
+
+    /// Records the events emitted on the current thread.
+    struct Recorder(Arc<Mutex<Seen>>);
+
+    #[derive(Default)]
+    struct Fields {
+        kind: String,
+        index: Option<u64>,
+        other: bool,
+    }
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            match field.name() {
+                "kind" => self.kind = value.to_string(),
+                _ => self.other = true,
+            }
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            match field.name() {
+                "index" => self.index = Some(value),
+                _ => self.other = true,
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, _value: &dyn fmt::Debug) {
+            if field.name() != "message" {
+                self.other = true;
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let level = *event.metadata().level();
+            let seen = (level, fields.kind, fields.index, fields.other);
+            self.0.lock().unwrap().push(seen);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn failures_emit_kind_and_index() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Recorder(seen.clone()), || {
+            assert!(base64_decode(&("A".repeat(12) + "!==")).is_err());
+            assert!(decode_strict_aligned("AAA").is_err());
+            let from = &crate::Alphabet::STANDARD;
+            assert!(crate::transcode("AAAAA!", from, from).is_err());
+            assert!(crate::decode_byte_at("AAAA$AAA", 3).is_err());
+            assert!(crate::decode_matches_prefix("AAAAAAA!", &[0; 4]).is_err());
+            assert!(base64_decode("QUJD").is_ok());
+        });
+
+        let invalid = |index| (Level::WARN, "InvalidByte".to_string(), Some(index), false);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                invalid(12),
+                (Level::WARN, "InvalidLength".to_string(), None, false),
+                invalid(5),
+                invalid(4),
+                invalid(7),
+            ]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Converting Base64 between alphabets
+
+use std::io::{self, Read, Write};
+
+use crate::decoder::outside_alphabet;
+use crate::stream::invalid_data;
+use crate::{Alphabet, DecodeError, DEFAULT_BUFFER_SIZE, INVALID, PADDING};
+
+/// Rewrites `input` from the alphabet `from` to the alphabet `to`, e.g. to
+/// migrate URL-safe tokens to the standard alphabet.
+///
+/// Each character is replaced by the character with the same sextet value
+/// in `to`, so nothing is decoded to bytes and encoded again. Padding is
+/// copied as is; its placement is not checked.
+///
+/// Example:
+/// ```
+/// use base64::{transcode, Alphabet};
+///
+/// let standard = transcode("-_8=", &Alphabet::URL_SAFE, &Alphabet::STANDARD).unwrap();
+/// assert_eq!(standard, "+/8=");
+/// ```
+///
+/// Parameters:
+/// - `input`: Base64 written with `from`.
+/// - `from`: The alphabet of `input`.
+/// - `to`: The alphabet to write the result in.
+///
+/// Returns: The rewritten string, or `DecodeError::InvalidByte` for the
+/// first character that is neither in `from` nor padding. As when
+/// decoding, it is `WrongAlphabet` for a character of the other RFC 4648
+/// alphabet and `NonAsciiChar` for one that is not ASCII.
+pub fn transcode(input: &str, from: &Alphabet, to: &Alphabet) -> Result<String, DecodeError> {
+    let mut out = String::with_capacity(input.len());
+    for (index, byte) in input.bytes().enumerate() {
+        let rewritten =
+            transcode_byte(from, to, index, byte).map_err(|err| err.report_str(input))?;
+        out.push(rewritten as char);
+    }
+    Ok(out)
+}
+
+/// Like `transcode`, but reads `r` and writes to `w` in chunks of
+/// `DEFAULT_BUFFER_SIZE`, so files of any size are converted in constant
+/// memory.
+///
+/// Parameters:
+/// - `from`: The alphabet of the input.
+/// - `to`: The alphabet to write the output in.
+/// - `r`: Where the Base64 is read from.
+/// - `w`: Where the rewritten Base64 is written.
+///
+/// Returns: An I/O error if reading or writing fails, or one of kind
+/// `InvalidData` wrapping the `DecodeError` for the first character that
+/// is neither in `from` nor padding. Its index counts from the start of
+/// the stream, and everything before it has been written already.
+pub fn transcode_stream<R: Read, W: Write>(
+    from: &Alphabet,
+    to: &Alphabet,
+    r: &mut R,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+    let mut offset = 0;
+    loop {
+        let read = match r.read(&mut buffer) {
+            Ok(0) => return w.flush(),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let chunk = &mut buffer[..read];
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            match transcode_byte(from, to, offset + i, *byte) {
+                Ok(rewritten) => *byte = rewritten,
+                Err(err) => {
+                    w.write_all(&chunk[..i])?;
+                    return Err(invalid_data(err));
+                }
+            }
+        }
+        w.write_all(chunk)?;
+        offset += read;
+    }
+}
+
+/// Rewrites the character found at `index`, passing padding through.
+fn transcode_byte(
+    from: &Alphabet,
+    to: &Alphabet,
+    index: usize,
+    byte: u8,
+) -> Result<u8, DecodeError> {
+    if byte == PADDING as u8 {
+        return Ok(byte);
+    }
+    let sextet = from.decode_byte(byte);
+    if sextet == INVALID {
+        return Err(outside_alphabet(from, index, byte));
+    }
+    Ok(to.encode_sextet(sextet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_encode, base64_encode_urlsafe, Variant};
+
+    #[test]
+    fn standard_and_url_safe_round_trip() {
+        let data: Vec<u8> = (0..=255u8).rev().collect();
+        let standard = base64_encode(&data);
+        let url_safe = transcode(&standard, &Alphabet::STANDARD, &Alphabet::URL_SAFE).unwrap();
+        assert_eq!(url_safe, base64_encode_urlsafe(&data));
+        assert_eq!(
+            transcode(&url_safe, &Alphabet::URL_SAFE, &Alphabet::STANDARD).unwrap(),
+            standard
+        );
+    }
+
+    #[test]
+    fn characters_outside_the_source_alphabet_are_rejected() {
+        assert_eq!(
+            transcode("ab+c", &Alphabet::URL_SAFE, &Alphabet::STANDARD),
+            Err(DecodeError::wrong_alphabet(2, b'+', Variant::UrlSafe))
+        );
+        assert_eq!(
+            transcode("ab-c", &Alphabet::STANDARD, &Alphabet::URL_SAFE),
+            crate::base64_decode("ab-c").map(|_| String::new())
+        );
+        assert_eq!(
+            transcode("ab$c", &Alphabet::STANDARD, &Alphabet::URL_SAFE),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+        assert_eq!(
+            transcode("\u{e9}", &Alphabet::STANDARD, &Alphabet::URL_SAFE),
+            Err(DecodeError::non_ascii_char(0, '\u{e9}'))
+        );
+    }
+
+    /// Hands out at most `chunk` bytes per read.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 13 % 251) as u8).collect();
+        let url_safe = base64_encode_urlsafe(&data);
+        for chunk in [1, 4095, DEFAULT_BUFFER_SIZE + 3] {
+            let mut reader = Trickle {
+                data: url_safe.as_bytes(),
+                chunk,
+            };
+            let mut out = Vec::new();
+            transcode_stream(
+                &Alphabet::URL_SAFE,
+                &Alphabet::STANDARD,
+                &mut reader,
+                &mut out,
+            )
+            .unwrap();
+            assert_eq!(out, base64_encode(&data).as_bytes());
+        }
+    }
+
+    #[test]
+    fn streaming_errors_count_from_the_start() {
+        let mut input = base64_encode(&[0xfb; 30_000]).into_bytes();
+        input[DEFAULT_BUFFER_SIZE + 5] = b'-';
+        let mut out = Vec::new();
+        let err = transcode_stream(
+            &Alphabet::STANDARD,
+            &Alphabet::URL_SAFE,
+            &mut &input[..],
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref::<DecodeError>(),
+            Some(&DecodeError::wrong_alphabet(
+                DEFAULT_BUFFER_SIZE + 5,
+                b'-',
+                Variant::Standard
+            ))
+        );
+        assert_eq!(out.len(), DEFAULT_BUFFER_SIZE + 5);
+    }
+}
@@ -0,0 +1,184 @@
+//! Checking Base64 without producing the decoded bytes
+
+use crate::decoder::DecodeState;
+use crate::{base64_decode, base64_encode, Alphabet, Base64Config, DecodeError, PADDING};
+
+/// Checks whether `data` is valid padded Base64 in the standard alphabet.
+pub fn is_valid_base64(data: &str) -> bool {
+    let mut validator = Validator::new();
+    validator.update(data);
+    validator.finish().is_ok()
+}
+
+/// Guesses whether `data` was Base64 encoded twice by mistake.
+///
+/// This is only advisory: it decodes `data` once and reports whether the
+/// result consists entirely of Base64 characters (standard or URL-safe,
+/// plus padding), which suggests another layer of encoding. Plain ASCII
+/// data such as `"abcd"` or a hex string passes this test too, so treat
+/// a `true` as a reason to warn, never to reject.
+///
+/// Returns: `false` if `data` is not valid Base64 or decodes to nothing.
+pub fn looks_double_encoded(data: &str) -> bool {
+    match base64_decode(data) {
+        Ok(decoded) => {
+            !decoded.is_empty()
+                && decoded.iter().all(|&b| {
+                    Alphabet::STANDARD.contains(b)
+                        || Alphabet::URL_SAFE.contains(b)
+                        || b == PADDING as u8
+                })
+        }
+        Err(_) => false,
+    }
+}
+
+/// Encodes `data` unless it already is Base64 text, guarding against
+/// encoding the same data twice.
+///
+/// `data` is passed through unchanged if it is UTF-8 and
+/// `is_valid_base64` accepts it. That includes plain text which merely
+/// happens to be valid Base64, such as `"abcd"`, so only use this where
+/// the input is known to be either binary or already encoded.
+///
+/// Returns: The Base64 text, and whether `data` had to be encoded to get
+/// it. Empty input is returned as is, reported as not encoded.
+pub fn encode_once(data: &[u8]) -> (String, bool) {
+    match std::str::from_utf8(data) {
+        Ok(text) if is_valid_base64(text) => (text.to_string(), false),
+        _ => (base64_encode(data), true),
+    }
+}
+
+/// Validates Base64 input that arrives in chunks, without keeping it or
+/// its decoded bytes around.
+///
+/// Memory use stays constant no matter how much input is fed, so a
+/// multi-GB file can be checked piece by piece. The first error found is
+/// kept and reported by `finish`; its index counts from the start of the
+/// first chunk. Chunks fed after an error are ignored.
+///
+/// Example:
+/// ```
+/// use base64::Validator;
+///
+/// let mut validator = Validator::new();
+/// validator.update("TWFueSBo");
+/// validator.update("YW5kcw==");
+/// assert!(validator.finish().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Validator {
+    config: Base64Config,
+    state: DecodeState,
+    offset: usize,
+    error: Option<DecodeError>,
+    scratch: Vec<u8>,
+}
+
+impl Validator {
+    /// Creates a validator that has seen no input yet.
+    pub fn new() -> Self {
+        Validator {
+            config: Base64Config::new(),
+            state: DecodeState::new(),
+            offset: 0,
+            error: None,
+            scratch: Vec::with_capacity(3),
+        }
+    }
+
+    /// Checks the next chunk of input.
+    pub fn update(&mut self, chunk: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        for (i, byte) in chunk.bytes().enumerate() {
+            let pushed = self
+                .state
+                .push(&self.config, self.offset + i, byte, &mut self.scratch);
+            self.scratch.clear();
+            if let Err(err) = pushed {
+                self.error = Some(err.in_str_at(chunk, self.offset));
+                return;
+            }
+        }
+        self.offset += chunk.len();
+    }
+
+    /// Ends the input and reports whether all of it was valid.
+    pub fn finish(mut self) -> Result<(), DecodeError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => self.state.finish(&self.config, &mut self.scratch),
+        }
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base64_encode;
+
+    #[test]
+    fn chunked_validation_matches_one_shot() {
+        let encoded = base64_encode(&(0..5000u32).map(|i| i as u8).collect::<Vec<_>>());
+        for chunk_len in [1, 3, 4, 77, 1024] {
+            let mut validator = Validator::new();
+            for chunk in encoded.as_bytes().chunks(chunk_len) {
+                validator.update(std::str::from_utf8(chunk).unwrap());
+            }
+            assert_eq!(validator.finish(), Ok(()));
+        }
+        assert!(is_valid_base64(&encoded));
+        assert!(is_valid_base64(""));
+        assert!(!is_valid_base64("TWF"));
+    }
+
+    #[test]
+    fn corrupt_chunk_reports_global_index() {
+        let mut validator = Validator::new();
+        validator.update("TWFueSBo");
+        validator.update("YW5k");
+        validator.update("cw!=");
+        validator.update("ignored");
+        assert_eq!(validator.finish(), Err(DecodeError::invalid_byte(14, b'!')));
+
+        let mut truncated = Validator::new();
+        truncated.update("TWFueSBoYW5kcw=");
+        assert_eq!(truncated.finish(), Err(DecodeError::InvalidPadding));
+    }
+
+    #[test]
+    fn double_encoding_is_detected() {
+        let once = crate::base64_encode(b"\x00\xffbinary\x7f");
+        let twice = crate::base64_encode(once.as_bytes());
+        assert!(!looks_double_encoded(&once));
+        assert!(looks_double_encoded(&twice));
+        assert!(looks_double_encoded(&crate::base64_encode(b"-_8=")));
+
+        assert!(!looks_double_encoded(&crate::base64_encode(
+            b"Hello, world!"
+        )));
+        assert!(!looks_double_encoded(""));
+        assert!(!looks_double_encoded("not base64"));
+    }
+
+    #[test]
+    fn encode_once_passes_base64_through() {
+        let binary = b"\x00\xffbinary\x7f";
+        let (encoded, applied) = encode_once(binary);
+        assert_eq!(encoded, base64_encode(binary));
+        assert!(applied);
+
+        assert_eq!(encode_once(encoded.as_bytes()), (encoded.clone(), false));
+        assert_eq!(encode_once(b"Hello!"), ("SGVsbG8h".to_string(), true));
+        assert_eq!(encode_once(b""), (String::new(), false));
+    }
+}
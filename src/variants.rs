@@ -0,0 +1,259 @@
+//! Named functions for the common alphabet and padding combinations
+//!
+//! `base64_encode` and `base64_decode` cover the standard padded variant;
+//! these cover the remaining three. Each one is a shorthand for the
+//! matching `Base64Config` constant. `encode_url_query` names the
+//! variant that suits URL query values, and `decode_detect` accepts
+//! either alphabet.
+
+use crate::{Base64Config, DecodeError, Padding, Variant};
+
+/// Encodes `data` with the standard alphabet and no padding.
+pub fn base64_encode_nopad(data: &[u8]) -> String {
+    Base64Config::STANDARD_NO_PAD.encode(data)
+}
+
+/// Decodes standard alphabet Base64 that carries no padding.
+///
+/// Returns: The decoded bytes, or a `DecodeError` if the input contains
+/// `=` or any character outside the standard alphabet.
+pub fn base64_decode_nopad(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::STANDARD_NO_PAD.decode_str(data)
+}
+
+/// Encodes `data` with the URL-safe alphabet (`-` and `_`) and padding.
+pub fn base64_encode_urlsafe(data: &[u8]) -> String {
+    Base64Config::URL_SAFE.encode(data)
+}
+
+/// Decodes padded URL-safe alphabet Base64.
+///
+/// Returns: The decoded bytes, or a `DecodeError` if padding is missing or
+/// a character is outside the URL-safe alphabet. `+` and `/` are reported
+/// as `DecodeError::WrongAlphabet`.
+pub fn base64_decode_urlsafe(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::URL_SAFE.decode_str(data)
+}
+
+/// Encodes `data` with the URL-safe alphabet and no padding.
+pub fn base64_encode_urlsafe_nopad(data: &[u8]) -> String {
+    Base64Config::URL_SAFE_NO_PAD.encode(data)
+}
+
+/// Decodes URL-safe alphabet Base64 that carries no padding.
+///
+/// Returns: The decoded bytes, or a `DecodeError` if the input contains
+/// `=` or any character outside the URL-safe alphabet.
+pub fn base64_decode_urlsafe_nopad(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::URL_SAFE_NO_PAD.decode_str(data)
+}
+
+/// Encodes `data` for use as a raw URL query value.
+///
+/// The output only holds `A-Z a-z 0-9 - _`, all of which RFC 3986 leaves
+/// unreserved, so it never needs percent encoding: the URL-safe alphabet
+/// without padding.
+pub fn encode_url_query(data: &[u8]) -> String {
+    Base64Config::URL_SAFE_NO_PAD.encode(data)
+}
+
+/// Decodes the output of `encode_url_query`.
+///
+/// Padding is accepted but not required, in case the value went through
+/// something that added it back.
+///
+/// Returns: The decoded bytes, or a `DecodeError` if a character is
+/// outside the URL-safe alphabet.
+pub fn decode_url_query(data: &str) -> Result<Vec<u8>, DecodeError> {
+    Base64Config::URL_SAFE_NO_PAD
+        .padding(Padding::Optional)
+        .decode_str(data)
+}
+
+/// The alphabet `decode_detect` found its input to be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphabetKind {
+    /// The input holds `+` or `/`.
+    Standard,
+    /// The input holds `-` or `_`.
+    UrlSafe,
+    /// The input only holds characters both alphabets share, so it decodes
+    /// the same either way.
+    Ambiguous,
+}
+
+/// Decodes `data` in whichever RFC 4648 alphabet it is written in, and
+/// reports which one that was.
+///
+/// The alphabet is told apart by the two characters in which they
+/// differ. Padding is optional, as URL-safe tokens often go without it.
+///
+/// Example:
+/// ```
+/// use base64::{decode_detect, AlphabetKind};
+///
+/// assert_eq!(decode_detect("-_8").unwrap(), (vec![0xfb, 0xff], AlphabetKind::UrlSafe));
+/// ```
+///
+/// Returns: The decoded bytes and their alphabet, or a `DecodeError`. A
+/// character of one alphabet after one of the other is reported as
+/// `DecodeError::WrongAlphabet`, expecting the alphabet seen first.
+pub fn decode_detect(data: &str) -> Result<(Vec<u8>, AlphabetKind), DecodeError> {
+    let mut detected = None;
+    for (index, byte) in data.bytes().enumerate() {
+        let variant = match byte {
+            b'+' | b'/' => Variant::Standard,
+            b'-' | b'_' => Variant::UrlSafe,
+            _ => continue,
+        };
+        match detected {
+            None => detected = Some(variant),
+            Some(expected) if expected != variant => {
+                return Err(DecodeError::wrong_alphabet(index, byte, expected).report_str(data));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let (config, kind) = match detected {
+        Some(Variant::UrlSafe) => (Base64Config::URL_SAFE, AlphabetKind::UrlSafe),
+        Some(Variant::Standard) => (Base64Config::STANDARD, AlphabetKind::Standard),
+        None => (Base64Config::STANDARD, AlphabetKind::Ambiguous),
+    };
+    let decoded = config.padding(Padding::Optional).decode_str(data)?;
+    Ok((decoded, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_encode};
+
+    type Encode = fn(&[u8]) -> String;
+    type Decode = fn(&str) -> Result<Vec<u8>, DecodeError>;
+
+    // RFC 4648 §10 test vectors, plus bytes that hit sextets 62 and 63.
+    const VECTORS: [(&[u8], &str); 8] = [
+        (b"", ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+        (b"\xfb\xef\xbe\xff", "++++/w=="),
+    ];
+
+    #[test]
+    fn all_four_variants_round_trip() {
+        let variants: [(Encode, Decode, bool, bool); 4] = [
+            (base64_encode, base64_decode, false, true),
+            (base64_encode_nopad, base64_decode_nopad, false, false),
+            (base64_encode_urlsafe, base64_decode_urlsafe, true, true),
+            (
+                base64_encode_urlsafe_nopad,
+                base64_decode_urlsafe_nopad,
+                true,
+                false,
+            ),
+        ];
+
+        for (encode, decode, url_safe, padded) in variants {
+            for (data, standard) in VECTORS {
+                let mut expected = standard.to_string();
+                if url_safe {
+                    expected = expected.replace('+', "-").replace('/', "_");
+                }
+                if !padded {
+                    expected = expected.trim_end_matches('=').to_string();
+                }
+                assert_eq!(encode(data), expected);
+                assert_eq!(decode(&expected).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn url_query_values_need_no_escaping() {
+        let data: Vec<u8> = (0..=255u8).rev().collect();
+        for len in 0..12 {
+            let encoded = encode_url_query(&data[..len]);
+            assert!(encoded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+            assert_eq!(decode_url_query(&encoded).unwrap(), &data[..len]);
+            assert_eq!(
+                decode_url_query(&base64_encode_urlsafe(&data[..len])).unwrap(),
+                &data[..len]
+            );
+        }
+        assert_eq!(encode_url_query(b"\xfb\xff"), "-_8");
+    }
+
+    #[test]
+    fn variants_reject_each_other() {
+        assert!(base64_decode_urlsafe("++++/w==").is_err());
+        assert!(base64_decode_urlsafe("----_w").is_err());
+        assert!(base64_decode_nopad("Zg==").is_err());
+        assert!(base64_decode_urlsafe_nopad("----_w==").is_err());
+        assert!(base64_decode("Zg").is_err());
+    }
+
+    #[test]
+    fn mixed_up_alphabets_name_the_expected_one() {
+        let err = base64_decode_urlsafe_nopad("Zm9v/w").unwrap_err();
+        assert_eq!(
+            err,
+            DecodeError::wrong_alphabet(4, b'/', crate::Variant::UrlSafe)
+        );
+        assert!(err.to_string().contains("Expected URL-safe alphabet"));
+
+        match base64_decode_nopad("Zm9v-w").unwrap_err() {
+            DecodeError::WrongAlphabet {
+                index, expected, ..
+            } => {
+                assert_eq!(index, 4);
+                assert_eq!(expected, crate::Variant::Standard);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        // Characters foreign to both alphabets are still plain invalid bytes.
+        assert_eq!(
+            base64_decode_urlsafe("Zm9v!w=="),
+            Err(DecodeError::invalid_byte(4, b'!'))
+        );
+    }
+
+    #[test]
+    fn detect_reports_the_alphabet_used() {
+        assert_eq!(
+            decode_detect("+/8=").unwrap(),
+            (vec![0xfb, 0xff], AlphabetKind::Standard)
+        );
+        assert_eq!(
+            decode_detect("-_8").unwrap(),
+            (vec![0xfb, 0xff], AlphabetKind::UrlSafe)
+        );
+        assert_eq!(
+            decode_detect("TWFueQ==").unwrap(),
+            (b"Many".to_vec(), AlphabetKind::Ambiguous)
+        );
+        assert_eq!(
+            decode_detect("").unwrap(),
+            (vec![], AlphabetKind::Ambiguous)
+        );
+    }
+
+    #[test]
+    fn detect_rejects_mixed_alphabets() {
+        assert_eq!(
+            decode_detect("ab_c+d"),
+            Err(DecodeError::wrong_alphabet(4, b'+', Variant::UrlSafe))
+        );
+        assert_eq!(
+            decode_detect("+/$/"),
+            Err(DecodeError::invalid_byte(2, b'$'))
+        );
+    }
+}